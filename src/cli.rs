@@ -0,0 +1,998 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
+use std::path::PathBuf;
+use chrono::{DateTime, Duration, Utc};
+
+use todo_reminder::config::{self, Config};
+use todo_reminder::dates::{self, DateSettings, DstChoice, Resolution, Zone};
+use todo_reminder::paths::{self, Locations};
+use todo_reminder::query::{self, Query, View};
+use todo_reminder::recurrence::{self, Recurrence};
+use todo_reminder::sort::{self, Grouping, ListOrder, SortKey};
+use todo_reminder::storage;
+use todo_reminder::tags::{self, Tag};
+use todo_reminder::task::{self, LookupError, Priority};
+use todo_reminder::tui;
+use todo_reminder::view::{self, TableStyle};
+use todo_reminder::{reminder, Task, TaskId, TaskStore};
+
+use crate::{daemon, shell};
+
+/// Exit status for errors while running a command (I/O, unknown task, ...).
+pub const EXIT_FAILURE: u8 = 1;
+/// Exit status for malformed command lines.
+pub const EXIT_USAGE: u8 = 2;
+
+pub const USAGE: &str = "\
+Usage: todo [--data-dir DIR] [--profile NAME] [COMMAND]
+
+Options:
+  --data-dir DIR                Keep task lists in DIR instead of $TODO_DATA_DIR or
+                                $XDG_DATA_HOME/todo_reminder (~/.local/share/todo_reminder)
+  --profile NAME                Use the task list named NAME instead of $TODO_PROFILE or
+                                'default'; a new name starts an empty list
+
+Commands:
+  shell                         Start the interactive menu (default)
+  tui                           Start the full-screen interface
+  daemon [--tag TAGS]           Show reminders as tasks fall due, following changes
+                                to the task file, until interrupted. Reminders are desktop
+                                notifications with Done and Snooze buttons when a session
+                                bus is available (unless reminders.notify is 'console'),
+                                else printed. Rules in sinks.txt beside the task file and in
+                                reminders.sinks also send them to commands, webhooks or e-mail
+  add DESCRIPTION [--due DATE] [--dst earlier|later] [--priority LEVEL] [--tags TAGS]
+      [--remind TIMES] [--repeat RULE]
+                                Add a task; DATE is 'YYYY-MM-DD HH:MM[:SS]' or an expression
+                                like 'tomorrow 9am', 'next fri' or 'in 2 hours', optionally
+                                followed by an IANA zone (Area/City). --dst picks the
+                                instant when DATE falls in a daylight saving change.
+                                LEVEL is low, normal (the default), high or urgent.
+                                +project, @context and #tag words in DESCRIPTION become
+                                tags; TAGS adds more, e.g. '+work @office'.
+                                TIMES lists how long before DATE to remind, e.g. '1d,30m,0',
+                                or 'none'; the default is reminders.lead_times, else '0'.
+                                RULE repeats the task, e.g. 'daily', 'every 2 weeks on mon,
+                                fri', 'monthly on the last friday', 'yearly 5 times' or an
+                                RFC 5545 RRULE such as 'FREQ=MONTHLY;BYMONTHDAY=-1'
+  list [--format FORMAT] [--priority LEVEL] [--tag TAGS] [--where QUERY] [--view NAME]
+       [--sort KEYS] [--group BY]
+                                List tasks, most urgent and soonest due first, optionally
+                                only those at LEVEL or above, carrying all of TAGS and
+                                matching QUERY and the saved view NAME (FORMAT: text for
+                                a table, long for every detail, csv, json, tasks). QUERY
+                                combines filters such as '+work', 'done', 'due:<tomorrow',
+                                'due:none', 'priority>=high' and
+                                'desc~invoice' with and, or, not and parentheses.
+                                KEYS orders the list, e.g. 'status,due:desc', using due,
+                                created, priority, description and status, each optionally
+                                followed by :asc or :desc; the default is list.sort, else
+                                priority then due date. BY groups text listings under
+                                headers: due, tag or project; the default is list.group.
+                                Tables fit the terminal and show overdue tasks in red and
+                                those due within a day in yellow, unless $NO_COLOR is set
+  views                         List saved views, including the built-in today, overdue
+                                and this-week
+  view save NAME QUERY          Save QUERY as the view NAME
+  view rm NAME                  Delete the saved view NAME
+  tags                          List all tags with the number of open tasks for each
+  done ID                       Mark task ID as done; ID may be any unique prefix. A
+                                repeating task is added again for its next occurrence
+  undone ID                     Reopen task ID
+  edit ID [--description TEXT] [--due DATE|none] [--dst earlier|later] [--priority LEVEL]
+          [--tags TAGS] [--untag TAGS] [--remind TIMES] [--repeat RULE|none]
+                                Change a task's description, due date, priority, tags,
+                                reminders or recurrence
+  reminders [--tag TAGS]        List reminders that fired and were not yet acknowledged,
+                                then the reminders still to come
+  ack ID                        Acknowledge the pending reminder for task ID
+  snooze ID WHEN                Remind about task ID again after WHEN, a duration like
+                                '10m' or '2h' or a time like 'tomorrow morning'
+  rm ID [--yes]                 Delete task ID, asking first unless --yes is given
+  export [--format FORMAT] [--output FILE] [--tag TAGS]
+                                Export tasks (FORMAT: csv, json, or tasks for the full
+                                task file format) to stdout or FILE
+  import FILE                   Add the tasks from FILE, in the task file format (such as
+                                tasks.csv or 'export --format tasks'), that are not
+                                already in the list
+  profiles                      List the task lists in the data directory
+  config                        Show the settings in effect and where each comes from
+  help                          Show this message
+
+Settings are read from config.toml in $TODO_CONFIG_DIR or $XDG_CONFIG_HOME/todo_reminder
+(~/.config/todo_reminder). $TODO_REMIND, $TODO_NOTIFY, $TODO_SORT, $TODO_GROUP,
+$TODO_STORE and $TODO_BACKUPS override reminders.lead_times, reminders.notify,
+list.sort, list.group, storage.backend and storage.backups.
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// An aligned table, one task per row.
+    Text,
+    /// Every detail of each task over several lines.
+    Long,
+    Csv,
+    Json,
+    /// The task file format, with every field, for backups and `import`.
+    Tasks,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Shell,
+    Tui,
+    Daemon { tags: Vec<Tag> },
+    Help,
+    Add {
+        description: String,
+        due_date: Option<DateTime<Utc>>,
+        priority: Priority,
+        tags: Vec<Tag>,
+        lead_times: Option<Vec<Duration>>,
+        recurrence: Option<Recurrence>,
+    },
+    List {
+        format: Format,
+        min_priority: Option<Priority>,
+        tags: Vec<Tag>,
+        query: Option<Query>,
+        view: Option<String>,
+        sort: Option<Vec<SortKey>>,
+        group: Option<Grouping>,
+    },
+    Views,
+    SaveView { name: String, query: String },
+    RemoveView { name: String },
+    Tags,
+    Done { id: String },
+    Undone { id: String },
+    Edit {
+        id: String,
+        description: Option<String>,
+        due_date: Option<Option<DateTime<Utc>>>,
+        priority: Option<Priority>,
+        add_tags: Vec<Tag>,
+        remove_tags: Vec<Tag>,
+        lead_times: Option<Vec<Duration>>,
+        recurrence: Option<Option<Recurrence>>,
+    },
+    Reminders { tags: Vec<Tag> },
+    Acknowledge { id: String },
+    Snooze { id: String, until: DateTime<Utc> },
+    Remove { id: String, confirmed: bool },
+    Export { format: Format, output: Option<PathBuf>, tags: Vec<Tag> },
+    Import { path: PathBuf },
+    Profiles,
+    Config,
+}
+
+impl Command {
+    /// Whether the command depends on the settings in `config.toml`.
+    pub fn uses_config(&self) -> bool {
+        !matches!(self, Command::Help | Command::Profiles)
+    }
+}
+
+/// The options given before the command.
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub data_dir: Option<PathBuf>,
+    pub profile: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    Usage(String),
+    Lookup(LookupError),
+    Io(io::Error),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::Lookup(_) | CliError::Io(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}", message),
+            CliError::Lookup(e) => write!(f, "{}", e),
+            CliError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<LookupError> for CliError {
+    fn from(e: LookupError) -> Self {
+        CliError::Lookup(e)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+/// Splits the options off the front of the arguments following the
+/// program name, returning them and the rest.
+pub fn parse_options(args: &[String]) -> Result<(Options, &[String]), CliError> {
+    let mut options = Options::default();
+    let mut rest = args;
+    while let Some((name, after)) = rest.split_first() {
+        if name != "--data-dir" && name != "--profile" {
+            break;
+        }
+        let (value, after) =
+            after.split_first().ok_or_else(|| CliError::Usage(format!("{} needs a value", name)))?;
+        if name == "--data-dir" {
+            options.data_dir = Some(PathBuf::from(value));
+        } else {
+            paths::check_profile_name(value).map_err(CliError::Usage)?;
+            options.profile = Some(value.clone());
+        }
+        rest = after;
+    }
+    Ok((options, rest))
+}
+
+/// Parses the arguments following the program name and options, reading
+/// dates with `settings`.
+pub fn parse(args: &[String], settings: &DateSettings) -> Result<Command, CliError> {
+    let (name, rest) = match args.split_first() {
+        Some((name, rest)) => (name.as_str(), rest),
+        None => return Ok(Command::Shell),
+    };
+
+    match name {
+        "shell" => {
+            split_args(rest, &[], &[], 0)?;
+            Ok(Command::Shell)
+        }
+        "tui" => {
+            split_args(rest, &[], &[], 0)?;
+            Ok(Command::Tui)
+        }
+        "daemon" => {
+            let args = split_args(rest, &["tag"], &[], 0)?;
+            Ok(Command::Daemon { tags: parse_tags(args.value("tag"))? })
+        }
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        "add" => {
+            let args = split_args(rest, &["due", "dst", "priority", "tags", "remind", "repeat"], &[], 1)?;
+            let due_date = match args.value("due") {
+                Some(input) => Some(parse_due_date(input, parse_dst(args.value("dst"))?, settings)?),
+                None => None,
+            };
+            let lead_times = args.value("remind").map(parse_lead_times).transpose()?;
+            let recurrence = args.value("repeat").map(|input| parse_recurrence(input, settings)).transpose()?;
+            if recurrence.is_some() && due_date.is_none() {
+                return Err(CliError::Usage("--repeat needs a due date to repeat from; pass --due".to_string()));
+            }
+            let priority = args.value("priority").map(parse_priority).transpose()?.unwrap_or_default();
+            Ok(Command::Add {
+                description: args.positional[0].clone(),
+                due_date,
+                priority,
+                tags: parse_tags(args.value("tags"))?,
+                lead_times,
+                recurrence,
+            })
+        }
+        "list" | "ls" => {
+            let args = split_args(rest, &["format", "priority", "tag", "where", "view", "sort", "group"], &[], 0)?;
+            Ok(Command::List {
+                format: parse_format(args.value("format"), Format::Text)?,
+                min_priority: args.value("priority").map(parse_priority).transpose()?,
+                tags: parse_tags(args.value("tag"))?,
+                query: args.value("where").map(|input| parse_query(input, settings)).transpose()?,
+                view: args.value("view").map(str::to_string),
+                sort: args.value("sort").map(parse_sort_keys).transpose()?,
+                group: args.value("group").map(parse_grouping).transpose()?,
+            })
+        }
+        "views" => {
+            split_args(rest, &[], &[], 0)?;
+            Ok(Command::Views)
+        }
+        "view" => match rest.split_first() {
+            Some((action, rest)) if action == "save" => {
+                let args = split_args(rest, &[], &[], 2)?;
+                let (name, text) = (&args.positional[0], &args.positional[1]);
+                if !query::is_view_name(name) {
+                    return Err(CliError::Usage(format!(
+                        "invalid view name '{}'; use letters, digits, '-' and '_'",
+                        name
+                    )));
+                }
+                parse_query(text, settings)?;
+                Ok(Command::SaveView { name: name.clone(), query: text.clone() })
+            }
+            Some((action, rest)) if action == "rm" => {
+                let args = split_args(rest, &[], &[], 1)?;
+                Ok(Command::RemoveView { name: args.positional[0].clone() })
+            }
+            _ => Err(CliError::Usage("expected 'view save NAME QUERY' or 'view rm NAME'".to_string())),
+        },
+        "tags" => {
+            split_args(rest, &[], &[], 0)?;
+            Ok(Command::Tags)
+        }
+        "done" => {
+            let args = split_args(rest, &[], &[], 1)?;
+            Ok(Command::Done { id: args.positional[0].clone() })
+        }
+        "undone" | "reopen" => {
+            let args = split_args(rest, &[], &[], 1)?;
+            Ok(Command::Undone { id: args.positional[0].clone() })
+        }
+        "edit" => {
+            let args = split_args(
+                rest,
+                &["description", "due", "dst", "priority", "tags", "untag", "remind", "repeat"],
+                &[],
+                1,
+            )?;
+            let due_date = match args.value("due") {
+                Some("none") => Some(None),
+                Some(input) => Some(Some(parse_due_date(input, parse_dst(args.value("dst"))?, settings)?)),
+                None => None,
+            };
+            let description = args.value("description").map(str::to_string);
+            let priority = args.value("priority").map(parse_priority).transpose()?;
+            let add_tags = parse_tags(args.value("tags"))?;
+            let remove_tags = parse_tags(args.value("untag"))?;
+            let lead_times = args.value("remind").map(parse_lead_times).transpose()?;
+            let recurrence = match args.value("repeat") {
+                Some("none") => Some(None),
+                Some(input) => Some(Some(parse_recurrence(input, settings)?)),
+                None => None,
+            };
+            if description.is_none()
+                && due_date.is_none()
+                && priority.is_none()
+                && add_tags.is_empty()
+                && remove_tags.is_empty()
+                && lead_times.is_none()
+                && recurrence.is_none()
+            {
+                return Err(CliError::Usage(
+                    "nothing to change; pass --description, --due, --priority, --tags, --untag, --remind or --repeat"
+                        .to_string(),
+                ));
+            }
+            Ok(Command::Edit {
+                id: args.positional[0].clone(),
+                description,
+                due_date,
+                priority,
+                add_tags,
+                remove_tags,
+                lead_times,
+                recurrence,
+            })
+        }
+        "reminders" => {
+            let args = split_args(rest, &["tag"], &[], 0)?;
+            Ok(Command::Reminders { tags: parse_tags(args.value("tag"))? })
+        }
+        "ack" => {
+            let args = split_args(rest, &[], &[], 1)?;
+            Ok(Command::Acknowledge { id: args.positional[0].clone() })
+        }
+        "snooze" => {
+            let args = split_args(rest, &[], &[], 2)?;
+            let until = dates::parse_snooze(&args.positional[1], settings).map_err(|e| CliError::Usage(e.to_string()))?;
+            Ok(Command::Snooze { id: args.positional[0].clone(), until })
+        }
+        "rm" | "remove" => {
+            let args = split_args(rest, &[], &["yes"], 1)?;
+            Ok(Command::Remove { id: args.positional[0].clone(), confirmed: args.flag("yes") })
+        }
+        "export" => {
+            let args = split_args(rest, &["format", "output", "tag"], &[], 0)?;
+            let format = parse_format(args.value("format"), Format::Csv)?;
+            if matches!(format, Format::Text | Format::Long) {
+                return Err(CliError::Usage("export supports --format csv, json or tasks".to_string()));
+            }
+            Ok(Command::Export {
+                format,
+                output: args.value("output").map(PathBuf::from),
+                tags: parse_tags(args.value("tag"))?,
+            })
+        }
+        "profiles" => {
+            split_args(rest, &[], &[], 0)?;
+            Ok(Command::Profiles)
+        }
+        "config" => {
+            split_args(rest, &[], &[], 0)?;
+            Ok(Command::Config)
+        }
+        "import" => {
+            let args = split_args(rest, &[], &[], 1)?;
+            Ok(Command::Import { path: PathBuf::from(&args.positional[0]) })
+        }
+        other => Err(CliError::Usage(format!("unknown command '{}'", other))),
+    }
+}
+
+/// Executes `command` for the profile at `locations`, with the settings in
+/// `config`, writing results to stdout. The task store is opened with
+/// `open_store` only for commands that read or write tasks, so that the
+/// others leave the data directory alone.
+pub fn run<S: TaskStore>(
+    command: Command,
+    open_store: impl FnOnce() -> io::Result<S>,
+    locations: &Locations,
+    config: &Config,
+) -> Result<(), CliError> {
+    // Not locked for the whole command: the shell and daemon print
+    // reminders from a background thread.
+    let mut out = io::stdout();
+
+    let mut store = match command {
+        Command::Help => {
+            write!(out, "{}", USAGE)?;
+            return Ok(());
+        }
+        Command::Profiles => {
+            for name in locations.profiles()? {
+                let marker = if name == locations.profile { "*" } else { " " };
+                writeln!(out, "{} {}", marker, name)?;
+            }
+            return Ok(());
+        }
+        Command::Config => {
+            let path = config::path()?;
+            let found = if path.exists() { "" } else { " (not found)" };
+            writeln!(out, "# Settings from {}{} and the environment", path.display(), found)?;
+            config.write(&mut out)?;
+            return Ok(());
+        }
+        _ => open_store()?,
+    };
+    let store = &mut store;
+
+    match command {
+        Command::Help | Command::Profiles | Command::Config => unreachable!("handled without the store"),
+        Command::Shell => shell::run(store, config)?,
+        Command::Tui => tui::run(store, config)?,
+        Command::Daemon { tags } => daemon::run(store, tags, config)?,
+        Command::Add { description, due_date, priority, tags, lead_times, recurrence } => {
+            let mut tasks = store.load()?;
+            let id = TaskId::generate();
+            let mut task = Task::new(id, description, due_date);
+            task.lead_times = lead_times.unwrap_or_else(|| config.lead_times.clone());
+            task.priority = priority;
+            task.add_tags(tags);
+            task.recurrence = recurrence;
+            tasks.push(task);
+            store.save(&tasks)?;
+            match due_date {
+                Some(date) => writeln!(out, "Added task {}, due {}", id.short(), dates::format_local(date, &config.dates))?,
+                None => writeln!(out, "Added task {}", id.short())?,
+            }
+        }
+        Command::List { format, min_priority, tags, query, view, sort, group } => {
+            let view = match view {
+                Some(name) => Some(find_view(store, &name, &config.dates)?),
+                None => None,
+            };
+            let mut tasks = store.load()?;
+            tasks.retain(|task| {
+                min_priority.is_none_or(|min| task.priority >= min)
+                    && tags::has_all(task, &tags)
+                    && query.as_ref().is_none_or(|query| query.matches(task))
+                    && view.as_ref().is_none_or(|view| view.matches(task))
+            });
+            let default = config.order.clone();
+            let order = ListOrder { keys: sort.unwrap_or(default.keys), grouping: group.or(default.grouping) };
+            match format {
+                Format::Text => view::write_ordered_table(&mut out, &mut tasks, &order, &config.table_style())?,
+                Format::Long => {
+                    sort::sort_tasks(&mut tasks, &order.keys);
+                    match order.grouping {
+                        Some(grouping) => {
+                            let groups = sort::group_tasks(&tasks, grouping, Utc::now(), Zone::Local, config.dates.week_start);
+                            view::write_groups(&mut out, &groups, &config.dates)?
+                        }
+                        None => view::write_task_list(&mut out, &tasks, &config.dates)?,
+                    }
+                }
+                _ => {
+                    sort::sort_tasks(&mut tasks, &order.keys);
+                    write_formatted(&mut out, &tasks, format, &config.dates)?;
+                }
+            }
+        }
+        Command::Views => {
+            let saved = load_views(store)?;
+            for view in query::all_views(&saved) {
+                let builtin = !saved.contains(&view);
+                writeln!(out, "{:<12} {}{}", view.name, view.query, if builtin { "  (built in)" } else { "" })?;
+            }
+        }
+        Command::SaveView { name, query } => {
+            let path = views_path(store)?;
+            let mut views = query::load_views(&path)?;
+            views.retain(|view| view.name != name);
+            views.push(View::new(&name, &query));
+            query::save_views(&path, &views)?;
+            writeln!(out, "Saved view '{}'", name)?;
+        }
+        Command::RemoveView { name } => {
+            let path = views_path(store)?;
+            let mut views = query::load_views(&path)?;
+            let count = views.len();
+            views.retain(|view| view.name != name);
+            if views.len() == count {
+                let builtin = query::builtin_views().iter().any(|view| view.name == name);
+                return Err(CliError::Usage(match builtin {
+                    true => format!("'{}' is a built-in view and cannot be removed", name),
+                    false => format!("no saved view named '{}'", name),
+                }));
+            }
+            query::save_views(&path, &views)?;
+            writeln!(out, "Removed view '{}'", name)?;
+        }
+        Command::Tags => {
+            let tasks = store.load()?;
+            let counts = tags::open_counts(&tasks);
+            if counts.is_empty() {
+                writeln!(out, "No tags.")?;
+            }
+            view::write_tag_counts(&mut out, &counts)?;
+        }
+        Command::Done { id } => complete_task(&mut out, store, &id, &config.dates)?,
+        Command::Undone { id } => {
+            let task = update_task(store, &id, Task::reopen)?;
+            writeln!(out, "Reopened task {}: {}", task.id.short(), task.description)?;
+        }
+        Command::Edit { id, description, due_date, priority, add_tags, remove_tags, lead_times, recurrence } => {
+            let mut tasks = store.load()?;
+            let index = task::find(&tasks, &id)?;
+            let task = &mut tasks[index];
+            if let Some(description) = description {
+                task.set_description(description);
+            }
+            task.add_tags(add_tags);
+            task.tags.retain(|tag| !remove_tags.contains(tag));
+            if let Some(due_date) = due_date {
+                task.set_due_date(due_date);
+            }
+            if let Some(priority) = priority {
+                task.priority = priority;
+            }
+            if let Some(lead_times) = lead_times {
+                task.lead_times = lead_times;
+            }
+            if let Some(recurrence) = recurrence {
+                task.recurrence = recurrence;
+            }
+            if task.recurrence.is_some() && task.due_date.is_none() {
+                return Err(CliError::Usage("a repeating task needs a due date; pass --due or --repeat none".to_string()));
+            }
+            store.save(&tasks)?;
+            writeln!(out, "Updated task {}: {}", tasks[index].id.short(), tasks[index].description)?;
+        }
+        Command::Reminders { tags } => {
+            let mut tasks = store.load()?;
+            tasks.retain(|task| tags::has_all(task, &tags));
+            let pending: Vec<Task> = reminder::pending(&tasks).into_iter().cloned().collect();
+            if pending.is_empty() {
+                writeln!(out, "No pending reminders.")?;
+            } else {
+                view::write_task_list(&mut out, &pending, &config.dates)?;
+            }
+            let upcoming = reminder::upcoming(&tasks);
+            if upcoming.is_empty() {
+                writeln!(out, "No upcoming reminders.")?;
+            } else {
+                writeln!(out, "Upcoming reminders:")?;
+                view::write_upcoming(&mut out, &upcoming, &config.dates)?;
+            }
+        }
+        Command::Acknowledge { id } => {
+            let task = update_task(store, &id, |task| task.reminder.acknowledge())?;
+            writeln!(out, "Acknowledged reminder for task {}: {}", task.id.short(), task.description)?;
+        }
+        Command::Snooze { id, until } => {
+            let task = update_task(store, &id, |task| task.reminder.snooze(until))?;
+            writeln!(out, "Snoozed task {} until {}", task.id.short(), dates::format_local(until, &config.dates))?;
+        }
+        Command::Remove { id, confirmed } => {
+            let mut tasks = store.load()?;
+            let index = task::find(&tasks, &id)?;
+            let short_id = tasks[index].id.short();
+            if !confirmed && !confirm(&format!("Delete task {} '{}'?", short_id, tasks[index].description))? {
+                writeln!(out, "Kept task {}", short_id)?;
+                return Ok(());
+            }
+            let task = tasks.remove(index);
+            store.save(&tasks)?;
+            writeln!(out, "Removed task {}: {}", short_id, task.description)?;
+        }
+        Command::Export { format, output, tags } => {
+            let mut tasks = store.load()?;
+            tasks.retain(|task| tags::has_all(task, &tags));
+            match output {
+                Some(path) => {
+                    let file = OpenOptions::new().write(true).create(true).truncate(true).open(&path)?;
+                    write_formatted(BufWriter::new(file), &tasks, format, &config.dates)?;
+                    writeln!(out, "Tasks have been exported to {}", path.display())?;
+                }
+                None => write_formatted(&mut out, &tasks, format, &config.dates)?,
+            }
+        }
+        Command::Import { path } => {
+            let imported = storage::read_tasks(BufReader::new(File::open(&path)?))?;
+            let mut tasks = store.load()?;
+            let before = tasks.len();
+            let total = imported.len();
+            for task in imported {
+                if task::position(&tasks, task.id).is_none() {
+                    tasks.push(task);
+                }
+            }
+            let added = tasks.len() - before;
+            store.save(&tasks)?;
+            write!(out, "Imported {} task{} from {}", added, if added == 1 { "" } else { "s" }, path.display())?;
+            match total - added {
+                0 => writeln!(out)?,
+                skipped => writeln!(out, " ({} already in the list)", skipped)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Marks the task `id` done, saying so, or that it already was.
+fn complete_task<W: Write, S: TaskStore>(
+    mut out: W,
+    store: &mut S,
+    id: &str,
+    settings: &DateSettings,
+) -> Result<(), CliError> {
+    let mut tasks = store.load()?;
+    let index = task::find(&tasks, id)?;
+    if tasks[index].is_completed() {
+        writeln!(out, "Task {} is already completed: {}", tasks[index].id.short(), tasks[index].description)?;
+        return Ok(());
+    }
+    let next = task::complete(&mut tasks, index, Utc::now());
+    store.save(&tasks)?;
+    writeln!(out, "Completed task {}: {}", tasks[index].id.short(), tasks[index].description)?;
+    if let Some(next) = next.map(|i| &tasks[i]) {
+        if let Some(due) = next.due_date {
+            writeln!(out, "Next occurrence is task {}, due {}", next.id.short(), dates::format_local(due, settings))?;
+        }
+    }
+    Ok(())
+}
+
+fn update_task<S: TaskStore, F: FnOnce(&mut Task)>(store: &mut S, id: &str, update: F) -> Result<Task, CliError> {
+    let mut tasks = store.load()?;
+    let index = task::find(&tasks, id)?;
+    update(&mut tasks[index]);
+    store.save(&tasks)?;
+    Ok(tasks.swap_remove(index))
+}
+
+/// Where views for `store` are saved; only stores backed by a file have one.
+fn views_path<S: TaskStore>(store: &S) -> Result<PathBuf, CliError> {
+    match store.path() {
+        Some(path) => Ok(query::views_path(path)),
+        None => Err(CliError::Usage("views can only be saved next to a task file".to_string())),
+    }
+}
+
+fn load_views<S: TaskStore>(store: &S) -> io::Result<Vec<View>> {
+    match store.path() {
+        Some(path) => query::load_views(&query::views_path(path)),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Looks up a saved or built-in view and parses its query.
+fn find_view<S: TaskStore>(store: &S, name: &str, settings: &DateSettings) -> Result<Query, CliError> {
+    let views = query::all_views(&load_views(store)?);
+    let view = views
+        .iter()
+        .find(|view| view.name == name)
+        .ok_or_else(|| CliError::Usage(format!("unknown view '{}'; run 'todo views' to list them", name)))?;
+    query::parse_query(&view.query, settings).map_err(|e| CliError::Usage(format!("view '{}': {}", name, e)))
+}
+
+/// Asks a yes/no question on the terminal. Without a terminal to ask on,
+/// destructive commands must be confirmed up front with `--yes`.
+fn confirm(question: &str) -> Result<bool, CliError> {
+    if !io::stdin().is_terminal() {
+        return Err(CliError::Usage("not a terminal; pass --yes to confirm".to_string()));
+    }
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn write_formatted<W: Write>(writer: W, tasks: &[Task], format: Format, settings: &DateSettings) -> io::Result<()> {
+    match format {
+        Format::Text => {
+            let style = TableStyle { dates: settings.clone(), ..TableStyle::plain() };
+            view::write_task_table(writer, tasks, Utc::now(), &style)
+        }
+        Format::Long => view::write_task_list(writer, tasks, settings),
+        Format::Csv => view::write_csv_export(writer, tasks),
+        Format::Json => view::write_json(writer, tasks),
+        Format::Tasks => storage::write_tasks(writer, tasks),
+    }
+}
+
+/// Positional arguments and options of one subcommand.
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+}
+
+/// Separates positional arguments from `--name value` / `--name=value`
+/// options and `--flag` switches, accepting only the names listed in
+/// `value_options` and `flag_options` and exactly `positional_count`
+/// positionals.
+fn split_args(
+    args: &[String],
+    value_options: &[&str],
+    flag_options: &[&str],
+    positional_count: usize,
+) -> Result<Args, CliError> {
+    let mut positional = Vec::new();
+    let mut options = HashMap::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            positional.extend(iter.by_ref().cloned());
+            break;
+        }
+        let name = match arg.strip_prefix("--") {
+            Some(name) => name,
+            None => {
+                positional.push(arg.clone());
+                continue;
+            }
+        };
+        let (name, inline_value) = match name.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (name, None),
+        };
+        if flag_options.contains(&name) && inline_value.is_none() {
+            options.insert(name.to_string(), String::new());
+            continue;
+        }
+        if !value_options.contains(&name) {
+            return Err(CliError::Usage(format!("unknown option '--{}'", name)));
+        }
+        let value = match inline_value {
+            Some(value) => value,
+            None => iter
+                .next()
+                .cloned()
+                .ok_or_else(|| CliError::Usage(format!("option '--{}' needs a value", name)))?,
+        };
+        options.insert(name.to_string(), value);
+    }
+
+    if positional.len() != positional_count {
+        return Err(CliError::Usage(format!(
+            "expected {} argument(s), found {}",
+            positional_count,
+            positional.len()
+        )));
+    }
+
+    Ok(Args { positional, options })
+}
+
+fn parse_format(value: Option<&str>, default: Format) -> Result<Format, CliError> {
+    match value {
+        None => Ok(default),
+        Some("text") => Ok(Format::Text),
+        Some("long") => Ok(Format::Long),
+        Some("csv") => Ok(Format::Csv),
+        Some("json") => Ok(Format::Json),
+        Some("tasks") => Ok(Format::Tasks),
+        Some(other) => Err(CliError::Usage(format!("unknown format '{}'", other))),
+    }
+}
+
+fn parse_dst(value: Option<&str>) -> Result<Option<DstChoice>, CliError> {
+    match value {
+        None => Ok(None),
+        Some("earlier") => Ok(Some(DstChoice::Earlier)),
+        Some("later") => Ok(Some(DstChoice::Later)),
+        Some(other) => Err(CliError::Usage(format!("--dst must be 'earlier' or 'later', not '{}'", other))),
+    }
+}
+
+fn parse_due_date(input: &str, dst: Option<DstChoice>, settings: &DateSettings) -> Result<DateTime<Utc>, CliError> {
+    let parsed = dates::parse_due_date(input, settings).map_err(|e| CliError::Usage(e.to_string()))?;
+    if let Some(instant) = parsed.resolution.unique() {
+        return Ok(instant);
+    }
+    if let Some(choice) = dst {
+        return Ok(parsed.resolution.choose(choice));
+    }
+
+    let (earlier, later) = (parsed.resolution.choose(DstChoice::Earlier), parsed.resolution.choose(DstChoice::Later));
+    let problem = match parsed.resolution {
+        Resolution::Ambiguous { .. } => "happens twice",
+        _ => "does not exist",
+    };
+    Err(CliError::Usage(format!(
+        "'{}' {} in {} because of a daylight saving change; \
+         pass --dst earlier ({}) or --dst later ({})",
+        input,
+        problem,
+        parsed.zone,
+        parsed.zone.format(earlier),
+        parsed.zone.format(later)
+    )))
+}
+
+/// Parses an optional list of tags; an absent option means no tags.
+fn parse_tags(input: Option<&str>) -> Result<Vec<Tag>, CliError> {
+    match input {
+        Some(input) => tags::parse_tag_list(input).map_err(|e| CliError::Usage(e.to_string())),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn parse_priority(input: &str) -> Result<Priority, CliError> {
+    input.parse().map_err(|e: task::ParsePriorityError| CliError::Usage(e.to_string()))
+}
+
+fn parse_recurrence(input: &str, settings: &DateSettings) -> Result<Recurrence, CliError> {
+    recurrence::parse_recurrence(input, settings).map_err(|e| CliError::Usage(e.to_string()))
+}
+
+fn parse_query(input: &str, settings: &DateSettings) -> Result<Query, CliError> {
+    query::parse_query(input, settings).map_err(|e| CliError::Usage(format!("invalid query: {}", e)))
+}
+
+fn parse_sort_keys(input: &str) -> Result<Vec<SortKey>, CliError> {
+    sort::parse_sort_keys(input).map_err(|e| CliError::Usage(e.to_string()))
+}
+
+fn parse_grouping(input: &str) -> Result<Grouping, CliError> {
+    input.parse().map_err(|e: sort::ParseSortError| CliError::Usage(e.to_string()))
+}
+
+fn parse_lead_times(input: &str) -> Result<Vec<Duration>, CliError> {
+    dates::parse_lead_times(input).map_err(|e| CliError::Usage(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use todo_reminder::MemoryStore;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    fn parse_words(words: &[&str]) -> Result<Command, CliError> {
+        parse(&args(words), &DateSettings::default())
+    }
+
+    fn usage_error(words: &[&str]) -> String {
+        match parse_words(words) {
+            Err(CliError::Usage(message)) => message,
+            other => panic!("expected a usage error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_options_and_flags() {
+        let command = parse_words(&["add", "Pay rent", "--priority", "high", "--tags=+home @desk"]).unwrap();
+        assert_eq!(
+            command,
+            Command::Add {
+                description: "Pay rent".to_string(),
+                due_date: None,
+                priority: Priority::High,
+                tags: tags::parse_tag_list("+home @desk").unwrap(),
+                lead_times: None,
+                recurrence: None,
+            }
+        );
+        assert_eq!(
+            parse_words(&["rm", "ab12", "--yes"]).unwrap(),
+            Command::Remove { id: "ab12".to_string(), confirmed: true }
+        );
+        assert_eq!(
+            parse_words(&["rm", "ab12"]).unwrap(),
+            Command::Remove { id: "ab12".to_string(), confirmed: false }
+        );
+        assert_eq!(parse_words(&[]).unwrap(), Command::Shell);
+        assert_eq!(parse_words(&["--help"]).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn splits_leading_options() {
+        let words = args(&["--profile", "work", "--data-dir", "/tmp/todo", "list"]);
+        let (options, rest) = parse_options(&words).unwrap();
+        assert_eq!(options.profile.as_deref(), Some("work"));
+        assert_eq!(options.data_dir, Some(PathBuf::from("/tmp/todo")));
+        assert_eq!(rest, &args(&["list"])[..]);
+        assert!(matches!(parse_options(&args(&["--profile"])), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn rejects_unknown_options_and_missing_values() {
+        assert_eq!(usage_error(&["list", "--colour", "red"]), "unknown option '--colour'");
+        assert_eq!(usage_error(&["list", "--format"]), "option '--format' needs a value");
+        assert_eq!(usage_error(&["done"]), "expected 1 argument(s), found 0");
+        assert_eq!(usage_error(&["done", "a", "b"]), "expected 1 argument(s), found 2");
+        assert_eq!(usage_error(&["frobnicate"]), "unknown command 'frobnicate'");
+        // A switch does not take a value.
+        assert_eq!(usage_error(&["rm", "ab12", "--yes=no"]), "unknown option '--yes'");
+    }
+
+    #[test]
+    fn keeps_quoted_arguments_whole() {
+        // The shell has already split the words; spaces and dashes inside
+        // one of them must survive.
+        let command = parse_words(&["edit", "ab12", "--description", "call -- Bob --now"]).unwrap();
+        match command {
+            Command::Edit { description, .. } => assert_eq!(description.as_deref(), Some("call -- Bob --now")),
+            other => panic!("unexpected {:?}", other),
+        }
+        let command = parse_words(&["add", "--", "--not an option"]).unwrap();
+        match command {
+            Command::Add { description, .. } => assert_eq!(description, "--not an option"),
+            other => panic!("unexpected {:?}", other),
+        }
+        let command = parse_words(&["view", "save", "work", "+work and not done"]).unwrap();
+        assert_eq!(command, Command::SaveView { name: "work".to_string(), query: "+work and not done".to_string() });
+    }
+
+    #[test]
+    fn completing_a_done_task_says_so() {
+        let mut task = Task::new(TaskId::generate(), "Pay rent".to_string(), None);
+        let done_at = Utc::now() - Duration::days(2);
+        task.complete(done_at);
+        let id = task.id.to_string();
+        let mut store = MemoryStore { tasks: vec![task] };
+        let mut out = Vec::new();
+        complete_task(&mut out, &mut store, &id, &DateSettings::default()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with(&format!("Task {} is already completed", store.tasks[0].id.short())), "{}", out);
+        assert_eq!(store.tasks[0].completed_at, Some(done_at));
+    }
+
+    #[test]
+    fn errors_map_to_exit_codes() {
+        assert_eq!(CliError::Usage("bad".to_string()).exit_code(), EXIT_USAGE);
+        assert_eq!(CliError::Lookup(LookupError::NotFound("ab12".to_string())).exit_code(), EXIT_FAILURE);
+        assert_eq!(CliError::from(io::Error::other("disk full")).exit_code(), EXIT_FAILURE);
+        assert_eq!(parse_words(&["list", "--format", "xml"]).unwrap_err().exit_code(), EXIT_USAGE);
+    }
+}
@@ -0,0 +1,558 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Utc, Weekday};
+use toml::{Table, Value};
+
+use crate::dates::{self, DateSettings};
+use crate::notification::Delivery;
+use crate::paths;
+use crate::reminder;
+use crate::sinks::{self, Rule};
+use crate::sort::{self, ListOrder};
+use crate::store;
+use crate::view::{Color, TableStyle, Theme};
+
+/// The name of the configuration file in [`paths::config_dir`].
+pub const FILE_NAME: &str = "config.toml";
+
+/// The settings the file may hold, by section.
+///
+/// Some things are deliberately left out. The names of the files in a
+/// profile directory (`tasks.csv`, `tasks.db`, `views.txt`, `sinks.txt`)
+/// are the layout `todo profiles` and `todo import` rely on, and the
+/// `--data-dir` and `--profile` options already choose where they go.
+/// Reminders fire when they fall due rather than on a polling interval,
+/// so there is no sleep to tune. The wording of reminders is fixed, since
+/// desktop notifications rely on it; a command sink gets each field of the
+/// task to word its own message from.
+const KEYS: [(&str, &[&str]); 6] = [
+    ("dates", &["input_formats", "output_format", "week_start"]),
+    ("reminders", &["lead_times", "notify", "sinks", "only_in_working_hours"]),
+    ("list", &["sort", "group", "soon"]),
+    ("theme", &["color", "overdue", "soon"]),
+    ("working_hours", &["start", "end", "days"]),
+    ("storage", &["backend", "backups"]),
+];
+
+/// Environment variables that override a setting from the file.
+const ENV: [(&str, &str, &str); 6] = [
+    ("TODO_REMIND", "reminders", "lead_times"),
+    ("TODO_NOTIFY", "reminders", "notify"),
+    ("TODO_SORT", "list", "sort"),
+    ("TODO_GROUP", "list", "group"),
+    ("TODO_STORE", "storage", "backend"),
+    ("TODO_BACKUPS", "storage", "backups"),
+];
+
+/// When task tables are colored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// When writing to a terminal and `NO_COLOR` is not set.
+    Auto,
+    Always,
+    Never,
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorMode::Auto => write!(f, "auto"),
+            ColorMode::Always => write!(f, "always"),
+            ColorMode::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// Where a profile's tasks are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The `tasks.csv` file.
+    Csv,
+    /// The `tasks.db` SQLite database.
+    Sqlite,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Csv => write!(f, "csv"),
+            Backend::Sqlite => write!(f, "sqlite"),
+        }
+    }
+}
+
+/// The days and times of day the user works.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkingHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub days: Vec<Weekday>,
+}
+
+impl WorkingHours {
+    /// Whether `at` falls within working hours in the local zone.
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let at = at.with_timezone(&Local).naive_local();
+        self.days.contains(&at.weekday()) && self.start <= at.time() && at.time() < self.end
+    }
+}
+
+impl Default for WorkingHours {
+    fn default() -> WorkingHours {
+        WorkingHours {
+            start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+        }
+    }
+}
+
+/// Where a setting's value came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Env(&'static str),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Env(name) => write!(f, "${}", name),
+        }
+    }
+}
+
+/// The settings for everything the user may want to change, read from
+/// `config.toml` and the environment over built-in defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// How due dates are read and shown. The start of working hours is
+    /// used as the time of dates given as just a day.
+    pub dates: DateSettings,
+    /// The lead times of new tasks created without their own.
+    pub lead_times: Vec<Duration>,
+    pub delivery: Delivery,
+    /// Sink rules used besides those in `sinks.txt`.
+    pub sinks: Vec<Rule>,
+    /// Reminders coming due outside working hours wait until they start.
+    pub only_in_working_hours: bool,
+    /// How lists are ordered unless a command says otherwise.
+    pub order: ListOrder,
+    /// How close a due date has to be to count as soon.
+    pub soon: Duration,
+    pub color: ColorMode,
+    pub theme: Theme,
+    pub working_hours: WorkingHours,
+    pub backend: Backend,
+    /// How many earlier versions of `tasks.csv` are kept.
+    pub backups: usize,
+    sources: HashMap<String, Source>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            dates: DateSettings::default(),
+            lead_times: reminder::default_lead_times(),
+            delivery: Delivery::Auto,
+            sinks: Vec::new(),
+            only_in_working_hours: false,
+            order: ListOrder::default(),
+            soon: Duration::days(1),
+            color: ColorMode::Auto,
+            theme: Theme::default(),
+            working_hours: WorkingHours::default(),
+            backend: Backend::Csv,
+            backups: store::DEFAULT_BACKUPS,
+            sources: HashMap::new(),
+        }
+    }
+}
+
+/// Where the configuration file is: `config.toml` in
+/// [`paths::config_dir`].
+pub fn path() -> io::Result<PathBuf> {
+    Ok(paths::config_dir()?.join(FILE_NAME))
+}
+
+impl Config {
+    /// Reads the file at `path`, if there is one, and then the environment
+    /// variables that override it: `TODO_REMIND`, `TODO_NOTIFY`,
+    /// `TODO_SORT`, `TODO_GROUP`, `TODO_STORE` and `TODO_BACKUPS`.
+    pub fn load(path: &Path) -> io::Result<Config> {
+        Config::load_with_env(path, |name| std::env::var(name).ok())
+    }
+
+    /// Loads the file at `path`, reading environment variables with `var`.
+    fn load_with_env(path: &Path, var: impl Fn(&str) -> Option<String>) -> io::Result<Config> {
+        let mut config = match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text, path)
+                .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), message)))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Config::default(),
+            Err(e) => return Err(e),
+        };
+        for (name, section, key) in ENV {
+            if let Some(value) = var(name) {
+                config
+                    .set(section, key, &Value::String(value))
+                    .map_err(|message| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", name, message)))?;
+                config.sources.insert(format!("{}.{}", section, key), Source::Env(name));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Parses the text of a configuration file read from `path`, rejecting
+    /// unknown sections and keys as well as invalid values.
+    pub fn parse(text: &str, path: &Path) -> Result<Config, String> {
+        let table: Table = text.parse().map_err(|e: toml::de::Error| e.to_string().trim_end().to_string())?;
+        let mut config = Config::default();
+        for (section, value) in &table {
+            let (section, keys) = KEYS.iter().find(|(name, _)| name == section).ok_or_else(|| {
+                format!("unknown section [{}]; expected {}", section, list(KEYS.iter().map(|(name, _)| *name)))
+            })?;
+            let values = value.as_table().ok_or_else(|| format!("'{}' must be a section, [{}]", section, section))?;
+            for (key, value) in values {
+                let key = keys.iter().find(|name| *name == key).ok_or_else(|| {
+                    format!("unknown key '{}' in [{}]; expected {}", key, section, list(keys.iter().copied()))
+                })?;
+                config.set(section, key, value).map_err(|message| format!("[{}] {}: {}", section, key, message))?;
+                config.sources.insert(format!("{}.{}", section, key), Source::File(path.to_path_buf()));
+            }
+        }
+        if config.working_hours.end <= config.working_hours.start {
+            return Err("[working_hours] end must be later than start".to_string());
+        }
+        Ok(config)
+    }
+
+    fn set(&mut self, section: &str, key: &str, value: &Value) -> Result<(), String> {
+        match (section, key) {
+            ("dates", "input_formats") => {
+                let formats = strings(value)?;
+                for format in &formats {
+                    dates::check_format(format).map_err(|e| e.to_string())?;
+                }
+                self.dates.input_formats = formats;
+            }
+            ("dates", "output_format") => {
+                let format = string(value)?;
+                dates::check_format(format).map_err(|e| e.to_string())?;
+                self.dates.output_format = format.to_string();
+            }
+            ("dates", "week_start") => self.dates.week_start = weekday(string(value)?)?,
+            ("reminders", "lead_times") => {
+                self.lead_times = dates::parse_lead_times(string(value)?).map_err(|e| e.to_string())?;
+            }
+            ("reminders", "notify") => {
+                self.delivery = Delivery::parse(string(value)?)
+                    .ok_or_else(|| format!("expected 'auto' or 'console', not {}", value))?;
+            }
+            ("reminders", "sinks") => {
+                self.sinks = strings(value)?.iter().map(|rule| sinks::parse_rule(rule)).collect::<Result<_, _>>()?;
+            }
+            ("reminders", "only_in_working_hours") => {
+                self.only_in_working_hours = value.as_bool().ok_or("must be true or false")?;
+            }
+            ("list", "sort") => {
+                self.order.keys = match string(value)?.trim() {
+                    "" => Vec::new(),
+                    keys => sort::parse_sort_keys(keys).map_err(|e| e.to_string())?,
+                };
+            }
+            ("list", "group") => {
+                self.order.grouping = match string(value)?.trim() {
+                    "" | "none" => None,
+                    grouping => Some(grouping.parse().map_err(|e: sort::ParseSortError| e.to_string())?),
+                };
+            }
+            ("list", "soon") => self.soon = duration(string(value)?)?,
+            ("theme", "color") => {
+                self.color = match string(value)?.trim().to_lowercase().as_str() {
+                    "auto" => ColorMode::Auto,
+                    "always" => ColorMode::Always,
+                    "never" => ColorMode::Never,
+                    _ => return Err(format!("expected 'auto', 'always' or 'never', not {}", value)),
+                };
+            }
+            ("theme", "overdue") => self.theme.overdue = color(string(value)?)?,
+            ("theme", "soon") => self.theme.soon = color(string(value)?)?,
+            ("working_hours", "start") => {
+                self.working_hours.start = time(string(value)?)?;
+                self.dates.day_start = self.working_hours.start;
+            }
+            ("working_hours", "end") => self.working_hours.end = time(string(value)?)?,
+            ("working_hours", "days") => {
+                let days = strings(value)?.iter().map(|day| weekday(day)).collect::<Result<Vec<_>, _>>()?;
+                if days.is_empty() {
+                    return Err("expected at least one day".to_string());
+                }
+                self.working_hours.days = days;
+            }
+            ("storage", "backend") => {
+                self.backend = match string(value)?.trim().to_lowercase().as_str() {
+                    "csv" => Backend::Csv,
+                    "sqlite" => Backend::Sqlite,
+                    _ => return Err(format!("expected 'csv' or 'sqlite', not {}", value)),
+                };
+            }
+            ("storage", "backups") => {
+                // The environment can only give it as text.
+                let count = match value {
+                    Value::Integer(count) if *count >= 0 => Some(*count as usize),
+                    Value::String(count) => count.trim().parse().ok(),
+                    _ => None,
+                };
+                self.backups = count.ok_or_else(|| format!("expected a number of files, not {}", value))?;
+            }
+            _ => unreachable!("unhandled setting {}.{}", section, key),
+        }
+        Ok(())
+    }
+
+    /// Where the setting `key`, written `section.key`, came from.
+    pub fn source(&self, key: &str) -> Source {
+        self.sources.get(key).cloned().unwrap_or(Source::Default)
+    }
+
+    /// The hours reminders are limited to, if they are.
+    pub fn reminder_hours(&self) -> Option<WorkingHours> {
+        if self.only_in_working_hours {
+            Some(self.working_hours.clone())
+        } else {
+            None
+        }
+    }
+
+    /// How to draw task tables on stdout.
+    pub fn table_style(&self) -> TableStyle {
+        let mut style = TableStyle::for_stdout();
+        match self.color {
+            ColorMode::Auto => {}
+            ColorMode::Always => style.color = true,
+            ColorMode::Never => style.color = false,
+        }
+        style.soon = self.soon;
+        style.theme = self.theme;
+        style.dates = self.dates.clone();
+        style
+    }
+
+    /// Writes every setting as `section.key = value`, in TOML syntax,
+    /// followed by where its value came from.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let text = |value: &dyn fmt::Display| Value::String(value.to_string());
+        let texts = |values: Vec<String>| Value::Array(values.into_iter().map(Value::String).collect());
+        let time = |time: NaiveTime| text(&time.format("%H:%M"));
+        let sort = self.order.keys.iter().map(ToString::to_string).collect::<Vec<_>>().join(",");
+        let lead_times = if self.lead_times.is_empty() {
+            "none".to_string()
+        } else {
+            self.lead_times.iter().map(|&lead_time| dates::format_lead_time(lead_time)).collect::<Vec<_>>().join(",")
+        };
+        let settings = [
+            ("dates.input_formats", texts(self.dates.input_formats.clone())),
+            ("dates.output_format", text(&self.dates.output_format)),
+            ("dates.week_start", text(&weekday_name(self.dates.week_start))),
+            ("reminders.lead_times", text(&lead_times)),
+            ("reminders.notify", text(&self.delivery)),
+            ("reminders.sinks", texts(self.sinks.iter().map(ToString::to_string).collect())),
+            ("reminders.only_in_working_hours", Value::Boolean(self.only_in_working_hours)),
+            ("list.sort", text(&sort)),
+            ("list.group", text(&self.order.grouping.map_or("none".to_string(), |grouping| grouping.to_string()))),
+            ("list.soon", text(&dates::format_lead_time(self.soon))),
+            ("theme.color", text(&self.color)),
+            ("theme.overdue", text(&self.theme.overdue)),
+            ("theme.soon", text(&self.theme.soon)),
+            ("working_hours.start", time(self.working_hours.start)),
+            ("working_hours.end", time(self.working_hours.end)),
+            ("working_hours.days", texts(self.working_hours.days.iter().map(|&day| weekday_name(day)).collect())),
+            ("storage.backend", text(&self.backend)),
+            ("storage.backups", Value::Integer(self.backups as i64)),
+        ];
+        for (key, value) in settings {
+            let line = format!("{} = {}", key, value);
+            writeln!(writer, "{:<48} # {}", line, self.source(key))?;
+        }
+        Ok(())
+    }
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+    value.as_str().ok_or_else(|| format!("expected a string, not {}", value))
+}
+
+fn strings(value: &Value) -> Result<Vec<String>, String> {
+    let values = value.as_array().ok_or_else(|| format!("expected a list of strings, not {}", value))?;
+    values.iter().map(|value| string(value).map(str::to_string)).collect()
+}
+
+fn weekday(name: &str) -> Result<Weekday, String> {
+    dates::parse_weekday(&name.trim().to_lowercase()).ok_or_else(|| format!("'{}' is not a day of the week", name))
+}
+
+fn weekday_name(day: Weekday) -> String {
+    let name = match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    };
+    name.to_string()
+}
+
+fn time(text: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(text.trim(), "%H:%M").map_err(|_| format!("'{}' is not a time of day such as '09:00'", text))
+}
+
+fn duration(text: &str) -> Result<Duration, String> {
+    match dates::parse_lead_times(text).map_err(|e| e.to_string())?.as_slice() {
+        [duration] => Ok(*duration),
+        _ => Err(format!("expected one length of time such as '1d', not '{}'", text)),
+    }
+}
+
+fn color(name: &str) -> Result<Color, String> {
+    Color::parse(name).ok_or_else(|| {
+        format!("unknown color '{}'; use {}", name, list(Color::ALL.iter().map(|color| color.to_string())))
+    })
+}
+
+/// Joins names as "a, b or c".
+fn list<T: fmt::Display>(names: impl Iterator<Item = T>) -> String {
+    let names: Vec<String> = names.map(|name| name.to_string()).collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => names.concat(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::Grouping;
+    use crate::view::Color;
+
+    #[test]
+    fn reads_settings_and_rejects_unknown_keys() {
+        let path = Path::new("/etc/todo/config.toml");
+        let config = Config::parse(
+            r#"
+            [dates]
+            input_formats = ["%d.%m.%Y %H:%M", "%d.%m.%Y"]
+            week_start = "sun"
+
+            [reminders]
+            lead_times = "1d, 0"
+            sinks = ["+work = webhook https://chat.example.com/hooks/todo"]
+
+            [list]
+            group = "due"
+
+            [theme]
+            overdue = "magenta"
+
+            [working_hours]
+            start = "08:30"
+            days = ["mon", "wed"]
+
+            [storage]
+            backend = "sqlite"
+            backups = 5
+            "#,
+            path,
+        )
+        .unwrap();
+        assert_eq!(config.dates.week_start, Weekday::Sun);
+        assert_eq!(config.dates.day_start, NaiveTime::from_hms_opt(8, 30, 0).unwrap());
+        assert_eq!(config.lead_times, [Duration::days(1), Duration::zero()]);
+        assert_eq!(config.order.grouping, Some(Grouping::Due));
+        assert_eq!(config.theme, Theme { overdue: Color::Magenta, soon: Color::Yellow });
+        assert_eq!(config.sinks.len(), 1);
+        assert_eq!((config.backend, config.backups), (Backend::Sqlite, 5));
+        assert_eq!(config.source("list.group"), Source::File(path.to_path_buf()));
+        assert_eq!(config.source("list.sort"), Source::Default);
+
+        let mut written = Vec::new();
+        config.write(&mut written).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.contains(r#"working_hours.days = ["monday", "wednesday"]"#), "{}", written);
+        assert!(written.contains(r#"list.sort = """#), "{}", written);
+        assert!(written.contains("storage.backups = 5 "), "{}", written);
+
+        let error = |text: &str| Config::parse(text, path).unwrap_err();
+        assert_eq!(
+            error("[theme]\ncolour = \"never\""),
+            "unknown key 'colour' in [theme]; expected color, overdue or soon"
+        );
+        assert!(error("[colors]\n").starts_with("unknown section [colors]"));
+        assert_eq!(error("[list]\nsoon = 3"), "[list] soon: expected a string, not 3");
+        assert!(error("[dates]\noutput_format = \"%Q\"").contains("not a valid date format"));
+        assert!(error("[working_hours]\nstart = \"18:00\"").contains("end must be later than start"));
+        assert!(error("[list\n").contains("TOML parse error"));
+    }
+
+    #[test]
+    fn rejects_values_of_the_wrong_type() {
+        let path = Path::new("config.toml");
+        let error = |text: &str| Config::parse(text, path).unwrap_err();
+        assert!(error("dates = \"iso\"").starts_with("'dates' must be a section"));
+        assert!(error("[dates]\ninput_formats = \"%d.%m.%Y\"").starts_with("[dates] input_formats:"));
+        assert!(error("[dates]\ninput_formats = [1]").starts_with("[dates] input_formats:"));
+        assert!(error("[dates]\nweek_start = \"someday\"").starts_with("[dates] week_start:"));
+        assert!(error("[reminders]\nonly_in_working_hours = \"yes\"").starts_with("[reminders] only_in_working_hours:"));
+        assert!(error("[reminders]\nlead_times = \"soon\"").starts_with("[reminders] lead_times:"));
+        assert!(error("[theme]\ncolor = \"sometimes\"").starts_with("[theme] color:"));
+        assert!(error("[working_hours]\ndays = \"mon\"").starts_with("[working_hours] days:"));
+        assert!(error("[storage]\nbackend = \"postgres\"").starts_with("[storage] backend:"));
+        assert!(error("[storage]\nbackups = -1").starts_with("[storage] backups:"));
+        assert!(error("[reminders]\nlead_times = \"1d\"\n[reminders.extra]\nx = 1").starts_with("unknown key 'extra'"));
+    }
+
+    #[test]
+    fn environment_overrides_file_and_missing_file_gives_defaults() {
+        let dir = std::env::temp_dir().join(format!("todo-config-{}", crate::TaskId::generate()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(FILE_NAME);
+        let no_env = |_: &str| None;
+
+        let config = Config::load_with_env(&path, no_env).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.source("reminders.lead_times"), Source::Default);
+
+        fs::write(&path, "[reminders]\nlead_times = \"1d\"\nnotify = \"console\"\n[list]\ngroup = \"tag\"\n").unwrap();
+        let env = |name: &str| match name {
+            "TODO_REMIND" => Some("30m".to_string()),
+            "TODO_SORT" => Some("due:desc".to_string()),
+            "TODO_BACKUPS" => Some("0".to_string()),
+            _ => None,
+        };
+        let config = Config::load_with_env(&path, env).unwrap();
+        assert_eq!(config.lead_times, [Duration::minutes(30)]);
+        assert_eq!(config.source("reminders.lead_times"), Source::Env("TODO_REMIND"));
+        assert_eq!(config.source("list.sort"), Source::Env("TODO_SORT"));
+        assert_eq!(config.delivery, Delivery::Console);
+        assert_eq!(config.source("reminders.notify"), Source::File(path.clone()));
+        assert_eq!(config.order.grouping, Some(Grouping::Tag));
+        assert_eq!(config.backups, 0);
+        assert_eq!(config.source("storage.backups"), Source::Env("TODO_BACKUPS"));
+
+        let error = Config::load_with_env(&path, |name| (name == "TODO_NOTIFY").then(|| "pigeon".to_string()));
+        assert!(error.unwrap_err().to_string().starts_with("TODO_NOTIFY:"));
+        fs::write(&path, "[list]\nsort = \"urgency\"\n").unwrap();
+        let error = Config::load_with_env(&path, no_env).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with(&path.display().to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+use std::io;
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use chrono::Utc;
+use notify::{RecursiveMode, Watcher};
+
+use todo_reminder::config::Config;
+use todo_reminder::notification::{self, Action, Notifier};
+use todo_reminder::scheduler::lock;
+use todo_reminder::sinks::{self, RoutedNotifier};
+use todo_reminder::store;
+use todo_reminder::tags::{self, Tag};
+use todo_reminder::{Scheduler, SharedTasks, Task, TaskId, TaskStore};
+
+/// Something the daemon loop has to react to.
+enum Event {
+    /// The task file was written by another process.
+    FileChanged,
+    /// A reminder fired, so its state must be saved.
+    Fired,
+    /// The user clicked a button on a desktop notification.
+    Action(TaskId, Action),
+}
+
+/// The tasks the daemon reminds about and the store they are kept in.
+struct Daemon<'a, S> {
+    store: &'a mut S,
+    tasks: SharedTasks,
+    /// The tasks as last saved or loaded.
+    saved: Vec<Task>,
+    scheduler: Scheduler,
+}
+
+impl<S: TaskStore> Daemon<'_, S> {
+    /// Saves what `event` changed and loads what other processes saved,
+    /// letting the scheduler know if that changed the tasks.
+    fn handle(&mut self, event: Event) -> io::Result<()> {
+        let acted = match event {
+            Event::FileChanged | Event::Fired => false,
+            Event::Action(id, action) => match notification::apply_action(&mut lock(&self.tasks), id, action, Utc::now()) {
+                Some(message) => {
+                    println!("{}", message);
+                    true
+                }
+                None => return Ok(()),
+            },
+        };
+        // Reminders that fired are saved before the file is read again,
+        // even when its change is noticed first, so they do not fire twice.
+        if store::sync(self.store, &self.tasks, &mut self.saved)? || acted {
+            self.scheduler.wake();
+        }
+        Ok(())
+    }
+}
+
+/// Shows reminders as tasks fall due until the process is interrupted, as
+/// desktop notifications if possible and on the console otherwise, and
+/// through any sinks configured beside the task file or in `config`,
+/// reloading the task list whenever its file changes and saving it after
+/// each reminder so it is not repeated after a restart. With `tags`, only
+/// tasks carrying all of them are reminded about, and if `config` says so,
+/// only during working hours.
+pub fn run<S: TaskStore>(store: &mut S, tags: Vec<Tag>, config: &Config) -> io::Result<()> {
+    let path = store
+        .path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "this task store cannot be watched"))?
+        .to_path_buf();
+    let saved = store.load()?;
+    let tasks: SharedTasks = Arc::new(Mutex::new(saved.clone()));
+    let (events, received) = mpsc::channel();
+
+    let mut rules = config.sinks.clone();
+    rules.extend(sinks::load_rules(&sinks::sinks_path(&path))?);
+    let acted = events.clone();
+    let desktop = notification::desktop_or_console(config.delivery, move |id, action| {
+        let _ = acted.send(Event::Action(id, action));
+    });
+    let mut notifier = RoutedNotifier::new(desktop, &rules);
+    let fired = events.clone();
+    let hours = config.reminder_hours();
+    let filter = move |task: &_| {
+        tags::has_all(task, &tags) && hours.as_ref().is_none_or(|hours| hours.contains(Utc::now()))
+    };
+    let scheduler = Scheduler::spawn_filtered(tasks.clone(), filter, move |task| {
+        if let Err(e) = notifier.notify(task, Utc::now()) {
+            eprintln!("todo: could not show a reminder: {}", e);
+        }
+        let _ = fired.send(Event::Fired);
+    });
+
+    // Watch the directory rather than the file so saves that replace the
+    // file are noticed too.
+    let file_name = path.file_name().map(|name| name.to_os_string());
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            if event.paths.iter().any(|p| p.file_name() == file_name.as_deref()) {
+                let _ = events.send(Event::FileChanged);
+            }
+        }
+    })
+    .map_err(io::Error::other)?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    watcher.watch(dir, RecursiveMode::NonRecursive).map_err(io::Error::other)?;
+
+    println!("Watching {} for due tasks. Press Ctrl-C to stop.", path.display());
+    let mut daemon = Daemon { store, tasks, saved, scheduler };
+    for event in received {
+        if let Err(e) = daemon.handle(event) {
+            eprintln!("todo: could not update {}: {}", path.display(), e);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use todo_reminder::FileStore;
+
+    #[test]
+    fn reminds_about_tasks_other_processes_add() {
+        let dir = std::env::temp_dir().join(format!("todo-daemon-{}", TaskId::generate()));
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("tasks.csv");
+        let mut store = FileStore::new(&path);
+        let saved = store.load().unwrap();
+        let tasks: SharedTasks = Arc::new(Mutex::new(saved.clone()));
+        let (fired, reminders) = mpsc::channel();
+        let scheduler = Scheduler::spawn(tasks.clone(), move |task| {
+            let _ = fired.send(task.description.clone());
+        });
+        let mut daemon = Daemon { store: &mut store, tasks, saved, scheduler };
+
+        let soon = Task::new(TaskId::generate(), "soon".to_string(), Some(Utc::now() + chrono::Duration::milliseconds(300)));
+        FileStore::new(&path).save(&[soon]).unwrap();
+        daemon.handle(Event::FileChanged).unwrap();
+        assert_eq!(lock(&daemon.tasks).len(), 1);
+        assert_eq!(reminders.recv_timeout(Duration::from_secs(5)).unwrap(), "soon");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reminders_fire_once_when_the_file_changes_first() {
+        let dir = std::env::temp_dir().join(format!("todo-daemon-{}", TaskId::generate()));
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("tasks.csv");
+        let due = Task::new(TaskId::generate(), "due".to_string(), Some(Utc::now()));
+        FileStore::new(&path).save(&[due]).unwrap();
+
+        let mut store = FileStore::new(&path);
+        let saved = store.load().unwrap();
+        let tasks: SharedTasks = Arc::new(Mutex::new(saved.clone()));
+        let (fired, reminders) = mpsc::channel();
+        let scheduler = Scheduler::spawn(tasks.clone(), move |task| {
+            let _ = fired.send(task.description.clone());
+        });
+        let mut daemon = Daemon { store: &mut store, tasks, saved, scheduler };
+        assert_eq!(reminders.recv_timeout(Duration::from_secs(5)).unwrap(), "due");
+
+        // Another process adds a task before the reminder is saved.
+        let mut other = FileStore::new(&path);
+        let mut theirs = other.load().unwrap();
+        theirs.push(Task::new(TaskId::generate(), "later".to_string(), None));
+        other.save(&theirs).unwrap();
+        daemon.handle(Event::FileChanged).unwrap();
+        daemon.handle(Event::Fired).unwrap();
+
+        assert!(reminders.recv_timeout(Duration::from_millis(200)).is_err());
+        assert_eq!(lock(&daemon.tasks).len(), 2);
+        assert!(other.load().unwrap()[0].reminder.pending);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
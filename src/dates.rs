@@ -0,0 +1,656 @@
+use std::error::Error;
+use std::fmt;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, SubsecRound, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::{Tz, TZ_VARIANTS};
+
+/// Formats accepted for wall-clock due dates, most specific first.
+const LOCAL_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"];
+
+/// Time of day used when only a day is given, unless configured otherwise.
+const DEFAULT_TIME: (u32, u32) = (9, 0);
+/// Time of day used for "end of day/week/month".
+const END_OF_DAY: (u32, u32) = (23, 59);
+/// How due dates are shown unless configured otherwise.
+const DEFAULT_OUTPUT_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// How dates are read and shown, as configured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateSettings {
+    /// Further `strftime` formats accepted for due dates, tried after the
+    /// built-in ones. A format without a time of day gives a day.
+    pub input_formats: Vec<String>,
+    /// The `strftime` format due dates are shown in.
+    pub output_format: String,
+    /// The first day of the week, for "next week" and "end of week".
+    pub week_start: Weekday,
+    /// The time of day used when only a day is given ("tomorrow", "fri").
+    pub day_start: NaiveTime,
+}
+
+impl Default for DateSettings {
+    fn default() -> DateSettings {
+        DateSettings {
+            input_formats: Vec::new(),
+            output_format: DEFAULT_OUTPUT_FORMAT.to_string(),
+            week_start: Weekday::Mon,
+            day_start: NaiveTime::from_hms_opt(DEFAULT_TIME.0, DEFAULT_TIME.1, 0).unwrap(),
+        }
+    }
+}
+
+/// Formats `instant` in the local zone in the output format of `settings`.
+pub fn format_local(instant: DateTime<Utc>, settings: &DateSettings) -> String {
+    instant.with_timezone(&Local).format(&settings.output_format).to_string()
+}
+
+/// Checks that `format` is a usable `strftime` format.
+pub fn check_format(format: &str) -> Result<(), ParseDateError> {
+    if format.trim().is_empty() || StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(ParseDateError { message: format!("'{}' is not a valid date format", format) });
+    }
+    Ok(())
+}
+
+/// The time zone a wall-clock due date is interpreted in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    /// The system's local zone (honours `TZ`).
+    Local,
+    /// An IANA zone such as `Europe/Berlin`.
+    Named(Tz),
+}
+
+impl Zone {
+    /// Parses an IANA zone name, ignoring case; `local` selects the
+    /// system zone.
+    pub fn parse(name: &str) -> Option<Zone> {
+        if name.eq_ignore_ascii_case("local") {
+            return Some(Zone::Local);
+        }
+        // chrono-tz only matches names case-insensitively behind a feature
+        // that needs a build-time dependency, so look the name up directly.
+        name.parse::<Tz>()
+            .ok()
+            .or_else(|| TZ_VARIANTS.iter().copied().find(|tz| tz.name().eq_ignore_ascii_case(name)))
+            .map(Zone::Named)
+    }
+
+    /// Maps a wall-clock time in this zone to UTC, reporting DST gaps
+    /// and overlaps instead of silently picking one side.
+    pub fn resolve(&self, naive: NaiveDateTime) -> Resolution {
+        match self {
+            Zone::Local => resolve_in(&Local, naive),
+            Zone::Named(tz) => resolve_in(tz, naive),
+        }
+    }
+
+    /// Returns the wall-clock reading of `instant` in this zone.
+    pub fn wall_clock(&self, instant: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Local => instant.with_timezone(&Local).naive_local(),
+            Zone::Named(tz) => instant.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// Formats `instant` as wall-clock time in this zone, with the zone
+    /// abbreviation or offset so DST alternatives can be told apart.
+    pub fn format(&self, instant: DateTime<Utc>) -> String {
+        match self {
+            Zone::Local => instant.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+            Zone::Named(tz) => instant.with_timezone(tz).format("%Y-%m-%d %H:%M:%S %Z").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Local => write!(f, "local time"),
+            Zone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+/// How a wall-clock time maps onto real instants.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    Unique(DateTime<Utc>),
+    /// The time occurs twice because clocks were turned back.
+    Ambiguous { earlier: DateTime<Utc>, later: DateTime<Utc> },
+    /// The time never occurs because clocks jumped forward. `earlier` reads
+    /// the time with the offset after the jump, `later` with the one before.
+    Skipped { earlier: DateTime<Utc>, later: DateTime<Utc> },
+}
+
+/// Which instant to use when a wall-clock time is ambiguous or skipped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DstChoice {
+    Earlier,
+    Later,
+}
+
+impl Resolution {
+    pub fn unique(&self) -> Option<DateTime<Utc>> {
+        match *self {
+            Resolution::Unique(instant) => Some(instant),
+            _ => None,
+        }
+    }
+
+    pub fn choose(&self, choice: DstChoice) -> DateTime<Utc> {
+        match (*self, choice) {
+            (Resolution::Unique(instant), _) => instant,
+            (Resolution::Ambiguous { earlier, .. }, DstChoice::Earlier)
+            | (Resolution::Skipped { earlier, .. }, DstChoice::Earlier) => earlier,
+            (Resolution::Ambiguous { later, .. }, DstChoice::Later)
+            | (Resolution::Skipped { later, .. }, DstChoice::Later) => later,
+        }
+    }
+}
+
+/// A due date typed by the user together with the zone it was read in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParsedDate {
+    pub zone: Zone,
+    pub resolution: Resolution,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseDateError {
+    message: String,
+}
+
+impl fmt::Display for ParseDateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ParseDateError {}
+
+/// Parses a due date relative to the current time in the local zone.
+///
+/// See [`parse_due_date_at`] for the accepted syntax.
+pub fn parse_due_date(input: &str, settings: &DateSettings) -> Result<ParsedDate, ParseDateError> {
+    parse_due_date_at(input, Utc::now(), Zone::Local, settings)
+}
+
+/// Parses a due date, reading relative expressions against `now`.
+///
+/// Accepts RFC 3339 (`2026-11-01T09:00:00+01:00`), a wall-clock time
+/// `YYYY-MM-DD HH:MM[:SS]`, or a natural expression such as `today 17:00`,
+/// `tomorrow`, `next monday 9am`, `fri`, `in 45 minutes` or `end of month`.
+/// Any of the wall-clock forms may be followed by an IANA zone name
+/// (`tomorrow 9am Europe/Berlin`); otherwise `zone` is used.
+///
+/// A bare weekday means the next such day after today. Expressions naming
+/// only a day are due at the start of the day in `settings` (09:00 by
+/// default), and "end of ..." at 23:59. The input formats in `settings` are
+/// tried after the built-in wall-clock ones.
+pub fn parse_due_date_at(
+    input: &str,
+    now: DateTime<Utc>,
+    zone: Zone,
+    settings: &DateSettings,
+) -> Result<ParsedDate, ParseDateError> {
+    let input = input.trim();
+    if let Ok(instant) = DateTime::parse_from_rfc3339(input) {
+        return Ok(ParsedDate { zone, resolution: Resolution::Unique(instant.with_timezone(&Utc)) });
+    }
+
+    let (text, zone) = match input.rsplit_once(char::is_whitespace) {
+        Some((text, name)) if name.contains('/') || name.eq_ignore_ascii_case("utc") || name.eq_ignore_ascii_case("local") => {
+            let zone = Zone::parse(name).ok_or_else(|| ParseDateError {
+                message: format!("unknown time zone '{}'", name),
+            })?;
+            (text.trim_end(), zone)
+        }
+        _ => (input, zone),
+    };
+
+    if let Some(naive) = LOCAL_FORMATS.iter().find_map(|format| NaiveDateTime::parse_from_str(text, format).ok()) {
+        return Ok(ParsedDate { zone, resolution: zone.resolve(naive) });
+    }
+    if let Some(naive) = settings.input_formats.iter().find_map(|format| parse_with_format(text, format, settings)) {
+        return Ok(ParsedDate { zone, resolution: zone.resolve(naive) });
+    }
+
+    match parse_natural(text, now, zone, settings)? {
+        Some(Natural::Instant(instant)) => Ok(ParsedDate {
+            zone,
+            resolution: Resolution::Unique(instant.trunc_subsecs(0)),
+        }),
+        Some(Natural::WallClock(naive)) => Ok(ParsedDate { zone, resolution: zone.resolve(naive) }),
+        None => Err(ParseDateError {
+            message: format!(
+                "could not understand date '{}'; try 'YYYY-MM-DD HH:MM', 'tomorrow 9am', 'next friday' or 'in 2 hours'",
+                input
+            ),
+        }),
+    }
+}
+
+/// Parses when a snoozed reminder should fire again, relative to now.
+///
+/// See [`parse_snooze_at`] for the accepted syntax.
+pub fn parse_snooze(input: &str, settings: &DateSettings) -> Result<DateTime<Utc>, ParseDateError> {
+    parse_snooze_at(input, Utc::now(), Zone::Local, settings)
+}
+
+/// Parses a snooze period: either a duration (`5m`, `1 hour`, `in 2h`) or
+/// any due date expression (`tomorrow morning`, `17:00`). The result must
+/// lie after `now`; wall-clock times in a DST change use the later instant.
+pub fn parse_snooze_at(
+    input: &str,
+    now: DateTime<Utc>,
+    zone: Zone,
+    settings: &DateSettings,
+) -> Result<DateTime<Utc>, ParseDateError> {
+    let text = input.trim().to_lowercase();
+    let duration = text.strip_prefix("in ").unwrap_or(&text);
+    let duration = duration.split_whitespace().collect::<String>();
+    let until = match parse_duration(&duration) {
+        Some(length) => add(now.trunc_subsecs(0), length?, &duration)?,
+        None => parse_due_date_at(input, now, zone, settings)?.resolution.choose(DstChoice::Later),
+    };
+    if until <= now {
+        return Err(ParseDateError { message: format!("'{}' is not in the future", input.trim()) });
+    }
+    Ok(until)
+}
+
+/// Parses a comma-separated list of reminder lead times such as
+/// `1d, 30m, 0`. Each entry is a duration before the due date; `0` or
+/// `due` means at the due time and `none` alone means no reminders. The
+/// result is sorted from earliest reminder to latest, without duplicates.
+pub fn parse_lead_times(input: &str) -> Result<Vec<Duration>, ParseDateError> {
+    let text = input.trim().to_lowercase();
+    if text == "none" {
+        return Ok(Vec::new());
+    }
+
+    let mut lead_times = Vec::new();
+    for entry in text.split(',') {
+        let entry: String = entry.split_whitespace().filter(|word| *word != "before").collect();
+        let lead_time = match entry.as_str() {
+            "0" | "due" | "atdue" => Some(Duration::zero()),
+            entry => parse_duration(entry).transpose()?,
+        };
+        match lead_time {
+            Some(lead_time) => lead_times.push(lead_time),
+            None => {
+                return Err(ParseDateError {
+                    message: format!(
+                        "could not understand reminder times '{}'; try '1d, 30m, 0' or 'none'",
+                        input.trim()
+                    ),
+                })
+            }
+        }
+    }
+    lead_times.sort_by(|a, b| b.cmp(a));
+    lead_times.dedup();
+    Ok(lead_times)
+}
+
+/// Formats a lead time in the largest unit that represents it exactly,
+/// e.g. `1d`, `90m` or `0m`, so that [`parse_lead_times`] reads it back.
+pub fn format_lead_time(lead_time: Duration) -> String {
+    let minutes = lead_time.num_minutes();
+    let (amount, unit) = if minutes != 0 && minutes % (7 * 24 * 60) == 0 {
+        (minutes / (7 * 24 * 60), "w")
+    } else if minutes != 0 && minutes % (24 * 60) == 0 {
+        (minutes / (24 * 60), "d")
+    } else if minutes != 0 && minutes % 60 == 0 {
+        (minutes / 60, "h")
+    } else {
+        (minutes, "m")
+    };
+    format!("{}{}", amount, unit)
+}
+
+/// Parses an expression naming a whole day, such as `today`, `fri`,
+/// `end of month` or `2026-11-01`, relative to `now` in `zone`. Returns
+/// `None` for anything else, including expressions with a time of day.
+pub fn parse_day_at(input: &str, now: DateTime<Utc>, zone: Zone, settings: &DateSettings) -> Option<NaiveDate> {
+    let text = input.to_lowercase();
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    match parse_day(&tokens, zone.wall_clock(now).date(), settings)? {
+        (date, _, []) => Some(date),
+        _ => None,
+    }
+}
+
+/// Parses `text` in a configured input format, using the start of the day
+/// if the format has no time of day.
+fn parse_with_format(text: &str, format: &str, settings: &DateSettings) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, format)
+        .ok()
+        .or_else(|| Some(NaiveDate::parse_from_str(text, format).ok()?.and_time(settings.day_start)))
+}
+
+enum Natural {
+    /// A fixed offset from now ("in 2 hours").
+    Instant(DateTime<Utc>),
+    /// A calendar day and time to be read in the target zone.
+    WallClock(NaiveDateTime),
+}
+
+fn parse_natural(
+    text: &str,
+    now: DateTime<Utc>,
+    zone: Zone,
+    settings: &DateSettings,
+) -> Result<Option<Natural>, ParseDateError> {
+    let text = text.to_lowercase();
+    let tokens: Vec<&str> = text
+        .split_whitespace()
+        .filter(|token| !matches!(*token, "at" | "on"))
+        .collect();
+
+    match tokens.as_slice() {
+        ["now"] => return Ok(Some(Natural::Instant(now))),
+        ["in", rest @ ..] => {
+            let text = rest.concat();
+            return match parse_duration(&text) {
+                Some(length) => Ok(Some(Natural::Instant(add(now, length?, &text)?))),
+                None => Ok(None),
+            };
+        }
+        _ => {}
+    }
+    Ok(parse_wall_clock(&tokens, now, zone, settings))
+}
+
+/// Parses a day, a time of day, or both.
+fn parse_wall_clock(tokens: &[&str], now: DateTime<Utc>, zone: Zone, settings: &DateSettings) -> Option<Natural> {
+    let today = zone.wall_clock(now).date();
+    let (date, default_time, rest) = parse_day(tokens, today, settings)?;
+    let time = match rest.concat().as_str() {
+        "" => NaiveTime::from_hms_opt(default_time.0, default_time.1, 0)?,
+        time => parse_time(time, settings)?,
+    };
+    Some(Natural::WallClock(date.and_time(time)))
+}
+
+/// Parses the day part at the start of `tokens`, returning the day, the time
+/// to use if none is given, and the unparsed remainder.
+fn parse_day<'a, 'b>(
+    tokens: &'a [&'b str],
+    today: NaiveDate,
+    settings: &DateSettings,
+) -> Option<(NaiveDate, (u32, u32), &'a [&'b str])> {
+    let day_start = settings.day_start;
+    let start = (day_start.hour(), day_start.minute());
+    let day = match tokens {
+        ["today", rest @ ..] => (today, start, rest),
+        ["tomorrow", rest @ ..] => (today.succ_opt()?, start, rest),
+        ["yesterday", rest @ ..] => (today.pred_opt()?, start, rest),
+        ["next", "week", rest @ ..] => (next_weekday(today, settings.week_start), start, rest),
+        ["next", day, rest @ ..] => (next_weekday(today, parse_weekday(day)?), start, rest),
+        ["end", "of", "day", rest @ ..] | ["eod", rest @ ..] => (today, END_OF_DAY, rest),
+        ["end", "of", "week", rest @ ..] | ["eow", rest @ ..] => (end_of_week(today, settings.week_start), END_OF_DAY, rest),
+        ["end", "of", "month", rest @ ..] | ["eom", rest @ ..] => (end_of_month(today)?, END_OF_DAY, rest),
+        [first, rest @ ..] => {
+            if let Some(weekday) = parse_weekday(first) {
+                (next_weekday(today, weekday), start, rest)
+            } else if let Ok(date) = NaiveDate::parse_from_str(first, "%Y-%m-%d") {
+                (date, start, rest)
+            } else {
+                // A bare time of day means today.
+                (today, start, tokens)
+            }
+        }
+        [] => return None,
+    };
+    Some(day)
+}
+
+pub(crate) fn parse_weekday(token: &str) -> Option<Weekday> {
+    let weekday = match token {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tues" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thur" | "thurs" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+/// The first `weekday` strictly after `today`.
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days_ahead = (7 + weekday.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64) % 7;
+    today + Duration::days(if days_ahead == 0 { 7 } else { days_ahead })
+}
+
+/// The last day of the week containing `today`, with weeks starting on
+/// `week_start`.
+pub(crate) fn end_of_week(today: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let start = week_start.num_days_from_monday();
+    let into_week = (7 + today.weekday().num_days_from_monday() - start) % 7;
+    today + Duration::days(6 - into_week as i64)
+}
+
+fn end_of_month(today: NaiveDate) -> Option<NaiveDate> {
+    let (year, month) = if today.month() == 12 { (today.year() + 1, 1) } else { (today.year(), today.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1)?.pred_opt()
+}
+
+/// Parses "45minutes", "2h", "3days" and similar (spaces already removed).
+/// Returns `None` if `text` is not a duration, and an error if it is one
+/// too long to represent.
+fn parse_duration(text: &str) -> Option<Result<Duration, ParseDateError>> {
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = text.split_at(split);
+    if amount.is_empty() {
+        return None;
+    }
+    let length: fn(i64) -> Option<Duration> = match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => Duration::try_minutes,
+        "h" | "hr" | "hrs" | "hour" | "hours" => Duration::try_hours,
+        "d" | "day" | "days" => Duration::try_days,
+        "w" | "week" | "weeks" => Duration::try_weeks,
+        _ => return None,
+    };
+    Some(amount.parse().ok().and_then(length).ok_or_else(|| too_far(text)))
+}
+
+/// Adds `length`, read from `text`, to `instant`.
+fn add(instant: DateTime<Utc>, length: Duration, text: &str) -> Result<DateTime<Utc>, ParseDateError> {
+    instant.checked_add_signed(length).ok_or_else(|| too_far(text))
+}
+
+fn too_far(text: &str) -> ParseDateError {
+    ParseDateError { message: format!("'{}' is too far away", text) }
+}
+
+/// Parses "17:00", "9am", "9:30pm", and named times like "noon" or "morning".
+fn parse_time(text: &str, settings: &DateSettings) -> Option<NaiveTime> {
+    match text {
+        "morning" => return Some(settings.day_start),
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "afternoon" => return NaiveTime::from_hms_opt(14, 0, 0),
+        "evening" => return NaiveTime::from_hms_opt(18, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    let (clock, meridiem) = if let Some(clock) = text.strip_suffix("am") {
+        (clock, Some(false))
+    } else if let Some(clock) = text.strip_suffix("pm") {
+        (clock, Some(true))
+    } else {
+        (text, None)
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None if meridiem.is_some() => (clock.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+    let hour = match meridiem {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn resolve_in<T: TimeZone>(tz: &T, naive: NaiveDateTime) -> Resolution {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(date) => Resolution::Unique(date.with_timezone(&Utc)),
+        LocalResult::Ambiguous(a, b) => {
+            let (a, b) = (a.with_timezone(&Utc), b.with_timezone(&Utc));
+            Resolution::Ambiguous { earlier: a.min(b), later: a.max(b) }
+        }
+        LocalResult::None => {
+            // Read the skipped time with the offsets in force a day either
+            // side of the gap.
+            let offset_before = offset_at(tz, naive - chrono::Duration::days(1));
+            let offset_after = offset_at(tz, naive + chrono::Duration::days(1));
+            let with_before = Utc.from_utc_datetime(&(naive - offset_before));
+            let with_after = Utc.from_utc_datetime(&(naive - offset_after));
+            Resolution::Skipped { earlier: with_before.min(with_after), later: with_before.max(with_after) }
+        }
+    }
+}
+
+fn offset_at<T: TimeZone>(tz: &T, naive: NaiveDateTime) -> chrono::Duration {
+    use chrono::Offset;
+    let offset = tz.offset_from_utc_datetime(&naive).fix();
+    chrono::Duration::seconds(offset.local_minus_utc() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(input: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(input).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn named_zone_is_honoured() {
+        let parsed = parse_due_date("2026-11-01 09:00 Europe/Berlin", &DateSettings::default()).unwrap();
+        assert_eq!(parsed.resolution, Resolution::Unique(utc("2026-11-01T08:00:00Z")));
+    }
+
+    #[test]
+    fn dst_gap_offers_both_sides() {
+        let parsed = parse_due_date("2026-03-29 02:30 Europe/Berlin", &DateSettings::default()).unwrap();
+        assert_eq!(
+            parsed.resolution,
+            Resolution::Skipped { earlier: utc("2026-03-29T00:30:00Z"), later: utc("2026-03-29T01:30:00Z") }
+        );
+    }
+
+    #[test]
+    fn dst_overlap_offers_both_instants() {
+        let parsed = parse_due_date("2026-10-25 02:30 Europe/Berlin", &DateSettings::default()).unwrap();
+        assert_eq!(
+            parsed.resolution,
+            Resolution::Ambiguous { earlier: utc("2026-10-25T00:30:00Z"), later: utc("2026-10-25T01:30:00Z") }
+        );
+    }
+
+    fn berlin(input: &str) -> Resolution {
+        // Wednesday 2026-10-14 15:30 in Berlin (CEST, UTC+2).
+        let now = utc("2026-10-14T13:30:00Z");
+        let zone = Zone::parse("Europe/Berlin").unwrap();
+        parse_due_date_at(input, now, zone, &DateSettings::default()).unwrap().resolution
+    }
+
+    #[test]
+    fn natural_expressions_resolve_against_now() {
+        assert_eq!(berlin("today 17:00"), Resolution::Unique(utc("2026-10-14T15:00:00Z")));
+        assert_eq!(berlin("tomorrow"), Resolution::Unique(utc("2026-10-15T07:00:00Z")));
+        assert_eq!(berlin("next monday 9am"), Resolution::Unique(utc("2026-10-19T07:00:00Z")));
+        assert_eq!(berlin("fri at 5:30pm"), Resolution::Unique(utc("2026-10-16T15:30:00Z")));
+        assert_eq!(berlin("wed"), Resolution::Unique(utc("2026-10-21T07:00:00Z")));
+        assert_eq!(berlin("in 45 minutes"), Resolution::Unique(utc("2026-10-14T14:15:00Z")));
+        assert_eq!(berlin("in 2h"), Resolution::Unique(utc("2026-10-14T15:30:00Z")));
+        assert_eq!(berlin("end of month"), Resolution::Unique(utc("2026-10-31T22:59:00Z")));
+        assert_eq!(berlin("2026-11-01"), Resolution::Unique(utc("2026-11-01T08:00:00Z")));
+        assert_eq!(berlin("noon"), Resolution::Unique(utc("2026-10-14T10:00:00Z")));
+    }
+
+    #[test]
+    fn natural_expressions_reject_nonsense() {
+        let now = utc("2026-10-14T13:30:00Z");
+        for input in ["someday", "next blursday", "in 3 fortnights", "tomorrow 25:00", "13pm"] {
+            assert!(parse_due_date_at(input, now, Zone::Local, &DateSettings::default()).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn snooze_accepts_durations_and_times() {
+        let now = utc("2026-10-14T13:30:00Z");
+        let zone = Zone::parse("Europe/Berlin").unwrap();
+        assert_eq!(parse_snooze_at("5m", now, zone, &DateSettings::default()).unwrap(), utc("2026-10-14T13:35:00Z"));
+        assert_eq!(parse_snooze_at("1 hour", now, zone, &DateSettings::default()).unwrap(), utc("2026-10-14T14:30:00Z"));
+        assert_eq!(parse_snooze_at("tomorrow morning", now, zone, &DateSettings::default()).unwrap(), utc("2026-10-15T07:00:00Z"));
+        assert!(parse_snooze_at("today 9am", now, zone, &DateSettings::default()).is_err());
+    }
+
+    #[test]
+    fn lead_times_round_trip() {
+        let parsed = parse_lead_times("30m, 1 day before, 0, 90 minutes").unwrap();
+        assert_eq!(parsed, vec![Duration::days(1), Duration::minutes(90), Duration::minutes(30), Duration::zero()]);
+        let formatted: Vec<String> = parsed.iter().map(|&d| format_lead_time(d)).collect();
+        assert_eq!(formatted, ["1d", "90m", "30m", "0m"]);
+        assert_eq!(parse_lead_times(&formatted.join(",")).unwrap(), parsed);
+        assert_eq!(parse_lead_times("none").unwrap(), vec![]);
+        assert!(parse_lead_times("soon").is_err());
+    }
+
+    #[test]
+    fn rejects_durations_too_long_to_represent() {
+        let now = utc("2026-10-14T13:30:00Z");
+        for input in ["in 99999999999 weeks", "in 9999999999999999 minutes", "in 99999999999999999999999 days"] {
+            let error = parse_due_date_at(input, now, Zone::Local, &DateSettings::default()).unwrap_err();
+            assert!(error.to_string().ends_with("is too far away"), "{}: {}", input, error);
+        }
+        // Representable as a duration, but not as a date from now.
+        assert!(parse_due_date_at("in 999999999 weeks", now, Zone::Local, &DateSettings::default()).is_err());
+        assert!(parse_snooze_at("99999999999 weeks", now, Zone::Local, &DateSettings::default()).is_err());
+        assert!(parse_lead_times("1d, 99999999999w").is_err());
+    }
+
+    #[test]
+    fn honours_date_settings() {
+        // Wednesday 2026-10-14.
+        let now = utc("2026-10-14T13:30:00Z");
+        let zone = Zone::parse("UTC").unwrap();
+        let settings = DateSettings {
+            input_formats: vec!["%d.%m.%Y".to_string()],
+            output_format: "%d.%m.%Y %H:%M".to_string(),
+            week_start: Weekday::Sun,
+            day_start: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+        };
+        let parse = |input| parse_due_date_at(input, now, zone, &settings).unwrap().resolution;
+        assert_eq!(parse("1.11.2026"), Resolution::Unique(utc("2026-11-01T08:00:00Z")));
+        assert_eq!(parse("tomorrow"), Resolution::Unique(utc("2026-10-15T08:00:00Z")));
+        assert_eq!(parse("next week"), Resolution::Unique(utc("2026-10-18T08:00:00Z")));
+        assert_eq!(parse("end of week"), Resolution::Unique(utc("2026-10-17T23:59:00Z")));
+        assert_eq!(parse_day_at("eow", now, zone, &DateSettings::default()), NaiveDate::from_ymd_opt(2026, 10, 18));
+        assert!(format_local(now, &settings).starts_with("14.10.2026 "));
+    }
+
+    #[test]
+    fn zone_names_ignore_case() {
+        assert_eq!(Zone::parse("europe/berlin"), Zone::parse("Europe/Berlin"));
+        assert_eq!(Zone::parse("LOCAL"), Some(Zone::Local));
+        let parsed = parse_due_date_at("tomorrow 9am utc", utc("2026-10-30T12:00:00Z"), Zone::Local, &DateSettings::default()).unwrap();
+        assert_eq!(parsed.resolution, Resolution::Unique(utc("2026-10-31T09:00:00Z")));
+        let parsed = parse_due_date("2026-11-01 09:00 europe/berlin", &DateSettings::default()).unwrap();
+        assert_eq!(parsed.resolution, Resolution::Unique(utc("2026-11-01T08:00:00Z")));
+    }
+
+    #[test]
+    fn rejects_unknown_zone() {
+        assert!(parse_due_date("2026-11-01 09:00 Mars/Olympus", &DateSettings::default()).is_err());
+    }
+}
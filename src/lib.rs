@@ -0,0 +1,31 @@
+//! Task model, persistence and reminders for the `todo_reminder` tool.
+//!
+//! Listing, parsing and storing tasks take a generic `Read`/`Write` rather
+//! than using the terminal, so the interactive binary is only a thin layer
+//! on top. The exception is [`tui`], the full-screen interface, which takes
+//! over the terminal while [`tui::run`] runs; its [`tui::App`] state can
+//! still be driven and drawn without one.
+
+pub mod config;
+pub mod dates;
+pub mod merge;
+pub mod notification;
+pub mod paths;
+pub mod query;
+pub mod recurrence;
+pub mod reminder;
+pub mod scheduler;
+pub mod sinks;
+pub mod sort;
+pub mod sqlite;
+pub mod storage;
+pub mod store;
+pub mod tags;
+pub mod task;
+pub mod tui;
+pub mod view;
+
+pub use scheduler::{Scheduler, SharedTasks};
+pub use sqlite::SqliteStore;
+pub use store::{FileStore, MemoryStore, TaskStore};
+pub use task::{Task, TaskId};
@@ -1,163 +1,97 @@
-use std::fs::OpenOptions;
-use std::io::{self, BufRead, Write};
-use std::time::Duration;
-use chrono::{DateTime, Local, Utc};
-
-struct Task {
-    description: String,
-    due_date: Option<DateTime<Utc>>,
-}
-
-impl Task {
-    fn new(description: String, due_date: Option<DateTime<Utc>>) -> Self {
-        Task { description, due_date }
-    }
-
-    fn is_due(&self) -> bool {
-        if let Some(due_date) = self.due_date {
-            let now = Utc::now();
-            now >= due_date
-        } else {
-            false
-        }
-    }
-}
-
-fn main() -> io::Result<()> {
-    let mut tasks: Vec<Task> = Vec::new();
-
-    // Load existing tasks
-    load_tasks(&mut tasks)?;
-
-    loop {
-        println!("Todo List Manager");
-        println!("1. Add a new task");
-        println!("2. View all tasks");
-        println!("3. Export tasks to CSV");
-        println!("4. Exit");
-        print!("Enter your choice: ");
-        io::Write::flush(&mut io::stdout())?;
-
-        let mut choice = String::new();
-        io::stdin().read_line(&mut choice)?;
-        match choice.trim() {
-            "1" => add_task(&mut tasks)?,
-            "2" => view_tasks(&tasks),
-            "3" => export_tasks_to_csv(&tasks)?,
-            "4" => {
-                save_tasks(&tasks)?;
-                break;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::ExitCode;
+
+use todo_reminder::config::{self, Backend, Config};
+use todo_reminder::dates::DateSettings;
+use todo_reminder::paths::{self, Locations};
+use todo_reminder::{sqlite, FileStore, TaskStore};
+
+mod cli;
+mod daemon;
+mod shell;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = cli::parse_options(&args).and_then(|(options, rest)| {
+        // Dates on the command line are read with the configured settings.
+        let (command, config) = match config::path().and_then(|path| Config::load(&path)) {
+            Ok(config) => (cli::parse(rest, &config.dates)?, config),
+            // Commands that do not use the settings still work when they
+            // cannot be read, so that 'todo help' can explain them.
+            Err(e) => match cli::parse(rest, &DateSettings::default()) {
+                Ok(command) if !command.uses_config() => (command, Config::default()),
+                _ => return Err(e.into()),
+            },
+        };
+        let locations = Locations::resolve(options.data_dir, options.profile)?;
+        cli::run(command, || open_store(&locations, &config), &locations, &config)
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("todo: {}", e);
+            if let cli::CliError::Usage(_) = e {
+                eprintln!("Run 'todo help' for usage.");
             }
-            _ => println!("Invalid choice. Please try again."),
+            ExitCode::from(e.exit_code())
         }
-
-        // Check for due tasks
-        for task in &tasks {
-            if task.is_due() {
-                println!("Reminder: Task '{}' is due!", task.description);
-            }
-        }
-
-        // Sleep for a short duration to avoid busy-waiting
-        std::thread::sleep(Duration::from_secs(1));
     }
-
-    Ok(())
 }
 
-fn add_task(tasks: &mut Vec<Task>) -> io::Result<()> {
-    print!("Enter task description: ");
-    io::Write::flush(&mut io::stdout())?;
-    let mut description = String::new();
-    io::stdin().read_line(&mut description)?;
-    let description = description.trim().to_string();
-
-    loop {
-        print!("Enter due date (optional, format YYYY-MM-DD HH:MM:SS) or leave blank for no due date: ");
-        io::Write::flush(&mut io::stdout())?;
-        let mut due_date_input = String::new();
-        io::stdin().read_line(&mut due_date_input)?;
-        let due_date_input = due_date_input.trim();
-
-        if due_date_input.is_empty() {
-            tasks.push(Task::new(description, None));
-            break;
-        } else {
-            match DateTime::parse_from_str(due_date_input, "%Y-%m-%d %H:%M:%S").map(|dt| dt.with_timezone(&Utc)) {
-                Ok(date) => {
-                    tasks.push(Task::new(description, Some(date)));
-                    break;
-                }
-                Err(e) => {
-                    println!("Invalid date format. Please try again. Error: {}", e);
-                }
-            }
-        }
+/// Opens the task store of the profile at `locations` that the
+/// `storage.backend` setting picks: the `tasks.csv` file, keeping
+/// `storage.backups` earlier versions of it, or the `tasks.db` SQLite
+/// database, which takes over the tasks in `tasks.csv` when it is first
+/// created. Once it has, `tasks.csv` is out of date, so it is not opened
+/// again while the database is there.
+fn open_store(locations: &Locations, config: &Config) -> io::Result<Box<dyn TaskStore>> {
+    let csv_path = locations.tasks_file();
+    let db_path = locations.tasks_database();
+    // Task lists used to be kept in the current directory.
+    let old_path = Path::new("tasks.csv");
+    if locations.profile == paths::DEFAULT_PROFILE && !csv_path.exists() && !db_path.exists() && old_path.exists() {
+        eprintln!(
+            "todo: tasks are now kept in {}; run 'todo import {}' to bring over the ones in the current directory",
+            locations.profile_dir().display(),
+            old_path.display()
+        );
     }
-
-    Ok(())
-}
-
-
-fn view_tasks(tasks: &Vec<Task>) {
-    for (i, task) in tasks.iter().enumerate() {
-        println!("Task {}: {}", i + 1, task.description);
-        if let Some(due_date) = task.due_date {
-            println!("Due date: {}", due_date.with_timezone(&Local));
-        } else {
-            println!("No due date");
-        }
-    }
-}
-
-fn load_tasks(tasks: &mut Vec<Task>) -> io::Result<()> {
-    let file = OpenOptions::new().read(true).open("tasks.csv");
-
-    if let Ok(file) = file {
-        for line in io::BufReader::new(file).lines() {
-            if let Ok(line) = line {
-                let parts: Vec<&str> = line.split(',').collect();
-                if parts.len() == 2 {
-                    let description = parts[0].to_string();
-                    let due_date = match parts[1].parse::<DateTime<Utc>>() {
-                        Ok(date) => Some(date),
-                        Err(_) => None,
-                    };
-                    tasks.push(Task::new(description, due_date));
-                }
+    fs::create_dir_all(locations.profile_dir())?;
+    match config.backend {
+        Backend::Csv => {
+            if db_path.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "tasks are kept in {}, not {}; set storage.backend to \"sqlite\", or run 'todo export --format tasks' with it and move the database away to go back",
+                        db_path.display(),
+                        csv_path.display()
+                    ),
+                ));
             }
+            let store = FileStore::new(&csv_path).with_backups(config.backups).on_warning(report);
+            Ok(Box::new(store))
         }
-    }
-
-    Ok(())
-}
-
-fn save_tasks(tasks: &Vec<Task>) -> io::Result<()> {
-    let mut file = OpenOptions::new().write(true).create(true).truncate(true).open("tasks.csv")?;
-
-    for task in tasks {
-        if let Some(due_date) = task.due_date {
-            writeln!(file, "{},{}", task.description, due_date)?;
-        } else {
-            writeln!(file, "{}", task.description)?;
+        Backend::Sqlite => {
+            let (store, migrated) = sqlite::open_migrating(&db_path, &csv_path)?;
+            let store = store.on_warning(report);
+            if let Some(count) = migrated {
+                eprintln!(
+                    "todo: copied {} tasks from {} into {}; the file is no longer used or updated, even if storage.backend goes back to \"csv\"",
+                    count,
+                    csv_path.display(),
+                    db_path.display()
+                );
+            }
+            Ok(Box::new(store))
         }
     }
-
-    Ok(())
 }
 
-fn export_tasks_to_csv(tasks: &Vec<Task>) -> io::Result<()> {
-    let mut file = OpenOptions::new().write(true).create(true).truncate(true).open("exported_tasks.csv")?;
-
-    writeln!(file, "Description,Due Date")?;
-    for task in tasks {
-        if let Some(due_date) = task.due_date {
-            writeln!(file, "{},{}", task.description, due_date)?;
-        } else {
-            writeln!(file, "{},", task.description)?;
-        }
-    }
-
-    println!("Tasks have been exported to exported_tasks.csv");
-    Ok(())
+/// Shows a warning from the task store.
+fn report(message: &str) {
+    eprintln!("todo: {}", message);
 }
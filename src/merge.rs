@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::task::{Task, TaskId};
+
+/// What one side did to a task both sides changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Edited,
+    Deleted,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Edited => write!(f, "edited"),
+            Change::Deleted => write!(f, "deleted"),
+        }
+    }
+}
+
+/// A task that was changed differently here and elsewhere since both last
+/// agreed. The change made here is the one kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub id: TaskId,
+    pub description: String,
+    pub ours: Change,
+    pub theirs: Change,
+}
+
+impl Conflict {
+    pub fn new(task: &Task, ours: Change, theirs: Change) -> Conflict {
+        Conflict { id: task.id, description: task.description.clone(), ours, theirs }
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task {} '{}' was {} elsewhere and {} here; the version from here was kept",
+            self.id.short(),
+            self.description,
+            self.theirs,
+            self.ours
+        )
+    }
+}
+
+/// The outcome of [`merge`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    pub tasks: Vec<Task>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Combines the changes made to `base` here, giving `ours`, and elsewhere,
+/// giving `theirs`, task by task.
+///
+/// A task only one side changed, added or deleted takes that side's
+/// version. Where both sides changed a task differently, ours wins and the
+/// task is reported as a conflict. Tasks keep the order of `ours`, with
+/// tasks only added elsewhere after them.
+pub fn merge(base: &[Task], ours: &[Task], theirs: &[Task]) -> Merged {
+    let index = |tasks: &[Task]| -> HashMap<TaskId, usize> {
+        tasks.iter().enumerate().map(|(i, task)| (task.id, i)).collect()
+    };
+    let (base_index, ours_index, theirs_index) = (index(base), index(ours), index(theirs));
+    let mut tasks = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for task in ours {
+        let before = base_index.get(&task.id).map(|&i| &base[i]);
+        let other = theirs_index.get(&task.id).map(|&i| &theirs[i]);
+        match (before, other) {
+            // Added here.
+            (None, None) => tasks.push(task.clone()),
+            (Some(before), None) => {
+                if task != before {
+                    conflicts.push(Conflict::new(task, Change::Edited, Change::Deleted));
+                    tasks.push(task.clone());
+                }
+            }
+            (Some(before), Some(other)) if task == before => tasks.push(other.clone()),
+            (_, Some(other)) => {
+                if before.is_some_and(|before| other != before) && other != task {
+                    conflicts.push(Conflict::new(task, Change::Edited, Change::Edited));
+                }
+                tasks.push(task.clone());
+            }
+        }
+    }
+    for other in theirs.iter().filter(|task| !ours_index.contains_key(&task.id)) {
+        match base_index.get(&other.id).map(|&i| &base[i]) {
+            // Added elsewhere.
+            None => tasks.push(other.clone()),
+            // Deleted here, unchanged elsewhere.
+            Some(before) if before == other => {}
+            Some(_) => conflicts.push(Conflict::new(other, Change::Deleted, Change::Edited)),
+        }
+    }
+    Merged { tasks, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Priority;
+
+    fn task(description: &str) -> Task {
+        Task::new(TaskId::generate(), description.to_string(), None)
+    }
+
+    #[test]
+    fn merges_task_by_task_and_reports_conflicts() {
+        let base = vec![task("a"), task("b"), task("c"), task("d"), task("e")];
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        // Each side edits one task, and both edit "c".
+        ours[0].priority = Priority::High;
+        theirs[1].priority = Priority::Low;
+        ours[2].description = "c here".to_string();
+        theirs[2].description = "c there".to_string();
+        // Deleted here but edited elsewhere, and the other way round.
+        ours.remove(3);
+        theirs[3].priority = Priority::Urgent;
+        ours[3].priority = Priority::Urgent;
+        theirs.remove(4);
+        ours.push(task("ours"));
+        theirs.push(task("theirs"));
+
+        let merged = merge(&base, &ours, &theirs);
+        let summary: Vec<(&str, Priority)> =
+            merged.tasks.iter().map(|task| (task.description.as_str(), task.priority)).collect();
+        assert_eq!(
+            summary,
+            [
+                ("a", Priority::High),
+                ("b", Priority::Low),
+                ("c here", Priority::Normal),
+                ("e", Priority::Urgent),
+                ("ours", Priority::Normal),
+                ("theirs", Priority::Normal),
+            ]
+        );
+        let conflicts: Vec<String> =
+            merged.conflicts.iter().map(|c| format!("{} {} {}", c.description, c.ours, c.theirs)).collect();
+        assert_eq!(conflicts, ["c here edited edited", "e edited deleted", "d deleted edited"]);
+        let message = merged.conflicts[0].to_string();
+        assert!(message.ends_with("'c here' was edited elsewhere and edited here; the version from here was kept"));
+
+        // Identical changes on both sides are not a conflict.
+        assert!(merge(&base, &ours, &ours).conflicts.is_empty());
+    }
+}
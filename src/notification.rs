@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use chrono::{DateTime, Duration, Utc};
+use zbus::blocking::Connection;
+use zbus::zvariant::Value;
+
+use crate::reminder;
+use crate::task::{self, Priority, Task, TaskId};
+
+/// How long the "Snooze" action puts a reminder off.
+pub const SNOOZE_MINUTES: i64 = 10;
+
+/// Something the user chose from a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Done,
+    Snooze,
+}
+
+impl Action {
+    fn key(self) -> &'static str {
+        match self {
+            Action::Done => "done",
+            Action::Snooze => "snooze",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Action> {
+        match key {
+            "done" => Some(Action::Done),
+            "snooze" => Some(Action::Snooze),
+            _ => None,
+        }
+    }
+}
+
+/// A way of telling the user a reminder fired.
+pub trait Notifier: Send {
+    fn notify(&mut self, task: &Task, now: DateTime<Utc>) -> io::Result<()>;
+}
+
+/// Prints reminders, as the interactive menu always has.
+pub struct ConsoleNotifier<W> {
+    writer: W,
+}
+
+impl<W: Write + Send> ConsoleNotifier<W> {
+    pub fn new(writer: W) -> Self {
+        ConsoleNotifier { writer }
+    }
+}
+
+impl<W: Write + Send> Notifier for ConsoleNotifier<W> {
+    fn notify(&mut self, task: &Task, now: DateTime<Utc>) -> io::Result<()> {
+        reminder::write_reminder(&mut self.writer, task, now)
+    }
+}
+
+/// Shows reminders through `primary`, or through `fallback` when that
+/// fails, for instance because no notification daemon is running.
+pub struct FallbackNotifier<P, F> {
+    primary: P,
+    fallback: F,
+}
+
+impl<P: Notifier, F: Notifier> FallbackNotifier<P, F> {
+    pub fn new(primary: P, fallback: F) -> Self {
+        FallbackNotifier { primary, fallback }
+    }
+}
+
+impl<P: Notifier, F: Notifier> Notifier for FallbackNotifier<P, F> {
+    fn notify(&mut self, task: &Task, now: DateTime<Utc>) -> io::Result<()> {
+        self.primary.notify(task, now).or_else(|_| self.fallback.notify(task, now))
+    }
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, &Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+/// Shows reminders as desktop notifications through the freedesktop
+/// `org.freedesktop.Notifications` service, with "Done" and "Snooze 10m"
+/// buttons.
+pub struct DbusNotifier {
+    proxy: NotificationsProxyBlocking<'static>,
+    /// Which task each notification still on screen is about.
+    shown: Arc<Mutex<HashMap<u32, TaskId>>>,
+}
+
+impl DbusNotifier {
+    /// Connects to the session bus. `on_action` is called from a background
+    /// thread whenever the user clicks a button.
+    pub fn session<F>(on_action: F) -> zbus::Result<DbusNotifier>
+    where
+        F: FnMut(TaskId, Action) + Send + 'static,
+    {
+        DbusNotifier::with_connection(&Connection::session()?, on_action)
+    }
+
+    /// Like [`DbusNotifier::session`], over an existing connection.
+    pub fn with_connection<F>(connection: &Connection, mut on_action: F) -> zbus::Result<DbusNotifier>
+    where
+        F: FnMut(TaskId, Action) + Send + 'static,
+    {
+        let proxy = NotificationsProxyBlocking::new(connection)?;
+        let shown: Arc<Mutex<HashMap<u32, TaskId>>> = Arc::default();
+
+        let actions = proxy.receive_action_invoked()?;
+        let clicked = shown.clone();
+        thread::spawn(move || {
+            for signal in actions {
+                let (id, action) = match signal.args() {
+                    Ok(args) => (args.id, Action::from_key(args.action_key)),
+                    Err(_) => continue,
+                };
+                let task = locked(&clicked).remove(&id);
+                if let (Some(task), Some(action)) = (task, action) {
+                    on_action(task, action);
+                }
+            }
+        });
+        let closed = proxy.receive_notification_closed()?;
+        let forgotten = shown.clone();
+        thread::spawn(move || {
+            for signal in closed {
+                if let Ok(args) = signal.args() {
+                    locked(&forgotten).remove(&args.id);
+                }
+            }
+        });
+
+        Ok(DbusNotifier { proxy, shown })
+    }
+}
+
+impl Notifier for DbusNotifier {
+    fn notify(&mut self, task: &Task, now: DateTime<Utc>) -> io::Result<()> {
+        let mut body = Vec::new();
+        reminder::write_reminder(&mut body, task, now)?;
+        let body = String::from_utf8_lossy(&body);
+        let body = body.trim().trim_start_matches("Reminder: ");
+        let urgency = Value::U8(urgency(task.priority));
+        let hints = HashMap::from([("urgency", &urgency)]);
+        let snooze = format!("Snooze {}m", SNOOZE_MINUTES);
+        let actions = [Action::Done.key(), "Done", Action::Snooze.key(), snooze.as_str()];
+        let id = self
+            .proxy
+            .notify("todo", 0, "appointment-soon", &task.description, body, &actions, hints, -1)
+            .map_err(io::Error::other)?;
+        locked(&self.shown).insert(id, task.id);
+        Ok(())
+    }
+}
+
+/// Locks `mutex`, carrying on with the data if another thread panicked
+/// while holding the lock.
+fn locked<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The freedesktop urgency level for a task: low, normal or, for urgent
+/// tasks, critical (which stays on screen until dismissed).
+pub fn urgency(priority: Priority) -> u8 {
+    match priority {
+        Priority::Low => 0,
+        Priority::Normal | Priority::High => 1,
+        Priority::Urgent => 2,
+    }
+}
+
+/// Where [`desktop_or_console`] shows reminders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// As desktop notifications if possible, else on the console.
+    Auto,
+    /// Always on the console.
+    Console,
+}
+
+impl Delivery {
+    /// Parses `auto` (or `desktop`) and `console`.
+    pub fn parse(name: &str) -> Option<Delivery> {
+        match name.trim().to_lowercase().as_str() {
+            "auto" | "desktop" => Some(Delivery::Auto),
+            "console" => Some(Delivery::Console),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Delivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Delivery::Auto => write!(f, "auto"),
+            Delivery::Console => write!(f, "console"),
+        }
+    }
+}
+
+/// Returns desktop notifications if a session bus is reachable and
+/// `delivery` allows them, else prints to stdout. Reminders the desktop
+/// fails to show, say because nothing on the bus displays notifications,
+/// are printed instead.
+pub fn desktop_or_console<F>(delivery: Delivery, on_action: F) -> Box<dyn Notifier>
+where
+    F: FnMut(TaskId, Action) + Send + 'static,
+{
+    if delivery == Delivery::Console {
+        return Box::new(ConsoleNotifier::new(io::stdout()));
+    }
+    match DbusNotifier::session(on_action) {
+        Ok(notifier) => Box::new(FallbackNotifier::new(notifier, ConsoleNotifier::new(io::stdout()))),
+        Err(_) => Box::new(ConsoleNotifier::new(io::stdout())),
+    }
+}
+
+/// Carries out `action` on the task with `id`, returning a description of
+/// what was done, or `None` if the task is gone. Completing a recurring
+/// task schedules its next occurrence.
+pub fn apply_action(tasks: &mut Vec<Task>, id: TaskId, action: Action, now: DateTime<Utc>) -> Option<String> {
+    let index = task::position(tasks, id)?;
+    match action {
+        Action::Done => {
+            task::complete(tasks, index, now);
+            Some(format!("Completed: {}", tasks[index].description))
+        }
+        Action::Snooze => {
+            let until = now + Duration::minutes(SNOOZE_MINUTES);
+            tasks[index].reminder.snooze(until);
+            Some(format!("Snoozed for {}m: {}", SNOOZE_MINUTES, tasks[index].description))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use std::os::unix::net::UnixStream;
+    use std::sync::mpsc;
+    use std::time::Duration as StdDuration;
+    use zbus::blocking::connection;
+    use zbus::object_server::SignalEmitter;
+
+    /// The summary, actions and urgency of a notification.
+    type Shown = (String, Vec<String>, u8);
+
+    /// Stands in for a notification daemon, recording what it was asked
+    /// to show.
+    #[derive(Default)]
+    struct MockServer {
+        shown: Arc<Mutex<Vec<Shown>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl MockServer {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &mut self,
+            _app_name: &str,
+            _replaces_id: u32,
+            _app_icon: &str,
+            summary: &str,
+            _body: &str,
+            actions: Vec<String>,
+            hints: HashMap<String, zbus::zvariant::OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let urgency = hints.get("urgency").and_then(|value| u8::try_from(value).ok()).unwrap_or(1);
+            let mut shown = locked(&self.shown);
+            shown.push((summary.to_string(), actions, urgency));
+            shown.len() as u32
+        }
+
+        #[zbus(signal)]
+        async fn action_invoked(emitter: &SignalEmitter<'_>, id: u32, action_key: &str) -> zbus::Result<()>;
+    }
+
+    /// Connects a client directly to a server offering `mock` at `path`,
+    /// and returns both ends.
+    fn connect(path: &'static str, mock: MockServer) -> (Connection, Connection) {
+        let (server_end, client_end) = UnixStream::pair().unwrap();
+        let guid = zbus::Guid::generate();
+        // The server handshake waits for the client, so build it alongside.
+        let server = thread::spawn(move || {
+            connection::Builder::async_io_unix_stream(server_end)
+                .server(guid)
+                .unwrap()
+                .p2p()
+                .serve_at(path, mock)
+                .unwrap()
+                .build()
+                .unwrap()
+        });
+        let client = connection::Builder::async_io_unix_stream(client_end).p2p().build().unwrap();
+        (client, server.join().unwrap())
+    }
+
+    #[test]
+    fn notifies_and_applies_actions_over_dbus() {
+        let shown = Arc::default();
+        let (client, server) = connect("/org/freedesktop/Notifications", MockServer { shown: Arc::clone(&shown) });
+
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let task = Task { priority: Priority::Urgent, ..Task::new(TaskId::generate(), "Pay rent".to_string(), Some(now)) };
+        let mut tasks = vec![task.clone()];
+        let (actions, received) = mpsc::channel();
+        let mut notifier = DbusNotifier::with_connection(&client, move |id, action| {
+            let _ = actions.send((id, action));
+        })
+        .unwrap();
+        notifier.notify(&task, now).unwrap();
+        assert_eq!(
+            *locked(&shown),
+            [("Pay rent".to_string(), vec!["done".to_string(), "Done".into(), "snooze".into(), "Snooze 10m".into()], 2)]
+        );
+
+        let iface = server.object_server().interface::<_, MockServer>("/org/freedesktop/Notifications").unwrap();
+        zbus::block_on(MockServer::action_invoked(iface.signal_emitter(), 1, "snooze")).unwrap();
+        let (id, action) = received.recv_timeout(StdDuration::from_secs(5)).unwrap();
+        assert_eq!((id, action), (task.id, Action::Snooze));
+        apply_action(&mut tasks, id, action, now).unwrap();
+        assert_eq!(tasks[0].reminder.snoozed_until, Some(now + Duration::minutes(SNOOZE_MINUTES)));
+
+        // A notification only answers once.
+        zbus::block_on(MockServer::action_invoked(iface.signal_emitter(), 1, "done")).unwrap();
+        assert!(received.recv_timeout(StdDuration::from_millis(200)).is_err());
+        assert_eq!(apply_action(&mut tasks, task.id, Action::Done, now).as_deref(), Some("Completed: Pay rent"));
+        assert!(tasks[0].is_completed());
+    }
+
+    #[test]
+    fn prints_reminders_nothing_on_the_bus_shows() {
+        // Nothing answers where notifications are sent.
+        let (client, _server) = connect("/elsewhere", MockServer::default());
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let task = Task::new(TaskId::generate(), "Pay rent".to_string(), Some(now));
+        let mut desktop = DbusNotifier::with_connection(&client, |_, _| {}).unwrap();
+        assert!(desktop.notify(&task, now).is_err());
+
+        let mut out = Vec::new();
+        FallbackNotifier::new(desktop, ConsoleNotifier::new(&mut out)).notify(&task, now).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Reminder: Task 'Pay rent' is due!\n");
+    }
+
+    #[test]
+    fn console_notifier_prints_reminders() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let mut out = Vec::new();
+        ConsoleNotifier::new(&mut out).notify(&Task::new(TaskId::generate(), "Call".to_string(), Some(now)), now).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Reminder: Task 'Call' is due!\n");
+        assert_eq!(urgency(Priority::Low), 0);
+    }
+}
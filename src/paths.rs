@@ -0,0 +1,135 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory name used under the XDG base directories.
+pub const APP_DIR: &str = "todo_reminder";
+/// The profile used unless another is chosen.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Where the task list of one profile is kept. Each profile has its own
+/// directory under the data directory, holding its tasks along with the
+/// views, sinks and backups that go with them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locations {
+    pub data_dir: PathBuf,
+    pub profile: String,
+}
+
+impl Locations {
+    /// Resolves the data directory and profile. The data directory is
+    /// `data_dir` if given, else `$TODO_DATA_DIR`, else
+    /// `$XDG_DATA_HOME/todo_reminder` (by default
+    /// `~/.local/share/todo_reminder`). The profile is `profile` if given,
+    /// else `$TODO_PROFILE`, else `default`.
+    pub fn resolve(data_dir: Option<PathBuf>, profile: Option<String>) -> io::Result<Locations> {
+        let from_env = || env::var_os("TODO_DATA_DIR").filter(|dir| !dir.is_empty()).map(PathBuf::from);
+        let data_dir = match data_dir.or_else(from_env) {
+            Some(dir) => dir,
+            None => xdg_dir(env::var_os("XDG_DATA_HOME"), env::var_os("HOME"), ".local/share")?.join(APP_DIR),
+        };
+        let profile = profile
+            .or_else(|| env::var("TODO_PROFILE").ok().filter(|name| !name.is_empty()))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        check_profile_name(&profile).map_err(|message| io::Error::new(io::ErrorKind::InvalidInput, message))?;
+        Ok(Locations { data_dir, profile })
+    }
+
+    pub fn profile_dir(&self) -> PathBuf {
+        self.data_dir.join(&self.profile)
+    }
+
+    /// The task file of the profile.
+    pub fn tasks_file(&self) -> PathBuf {
+        self.profile_dir().join("tasks.csv")
+    }
+
+    /// The SQLite database of the profile.
+    pub fn tasks_database(&self) -> PathBuf {
+        self.profile_dir().join("tasks.db")
+    }
+
+    /// The names of the profiles that have a directory, sorted.
+    pub fn profiles(&self) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.data_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                if let Some(name) = entry.file_name().to_str().filter(|name| check_profile_name(name).is_ok()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// The directory for configuration files: `$TODO_CONFIG_DIR`, else
+/// `$XDG_CONFIG_HOME/todo_reminder` (by default `~/.config/todo_reminder`).
+pub fn config_dir() -> io::Result<PathBuf> {
+    match env::var_os("TODO_CONFIG_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => Ok(xdg_dir(env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME"), ".config")?.join(APP_DIR)),
+    }
+}
+
+/// Profile names become directory names, so they must be one plain path
+/// component.
+pub fn check_profile_name(name: &str) -> Result<(), String> {
+    let plain = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\'])
+        && Path::new(name).components().count() == 1;
+    if plain {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a valid profile name: it cannot be empty, start with '.' or contain '/' or '\\'", name))
+    }
+}
+
+/// An XDG base directory: `value` if it is an absolute path (the
+/// specification says to ignore relative ones), else `fallback` under
+/// `home`.
+fn xdg_dir(value: Option<OsString>, home: Option<OsString>, fallback: &str) -> io::Result<PathBuf> {
+    if let Some(dir) = value.map(PathBuf::from).filter(|dir| dir.is_absolute()) {
+        return Ok(dir);
+    }
+    match home.filter(|home| !home.is_empty()) {
+        Some(home) => Ok(PathBuf::from(home).join(fallback)),
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "cannot find the home directory; set HOME, or TODO_DATA_DIR or --data-dir",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_xdg_and_checks_profile_names() {
+        let home = Some(OsString::from("/home/ann"));
+        assert_eq!(xdg_dir(None, home.clone(), ".local/share").unwrap(), Path::new("/home/ann/.local/share"));
+        assert_eq!(xdg_dir(Some("relative".into()), home.clone(), ".config").unwrap(), Path::new("/home/ann/.config"));
+        assert_eq!(xdg_dir(Some("/xdg/data".into()), home, ".local/share").unwrap(), Path::new("/xdg/data"));
+        assert!(xdg_dir(None, None, ".config").is_err());
+
+        let locations = Locations::resolve(Some(PathBuf::from("/data")), Some("work".to_string())).unwrap();
+        assert_eq!(locations.tasks_file(), Path::new("/data/work/tasks.csv"));
+        for name in ["", "..", ".hidden", "a/b"] {
+            assert!(check_profile_name(name).is_err(), "{}", name);
+        }
+        assert!(Locations::resolve(Some(PathBuf::from("/data")), Some("../x".to_string())).is_err());
+    }
+}
@@ -0,0 +1,635 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+
+use crate::dates::{self, DateSettings, DstChoice, Zone};
+use crate::tags::{Tag, TagKind};
+use crate::task::{Priority, Task};
+
+/// How a field is compared with a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// The stretch of time a due date is compared with: a whole day for
+/// values like `today`, or a single second for `now` or `2026-11-01 09:00`.
+/// `start` is included and `end` is not.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// A parsed filter expression, as produced by [`parse_query`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// The empty query, which matches every task.
+    All,
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Done,
+    Recurring,
+    Tag(Tag),
+    NoDueDate,
+    Due(Comparison, Span),
+    Priority(Comparison, Priority),
+    /// Case-insensitive substring of the description (kept lower case).
+    DescriptionContains(String),
+    /// Case-insensitive match of the whole description (kept lower case).
+    DescriptionEquals(String),
+}
+
+impl Query {
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Query::All => true,
+            Query::And(a, b) => a.matches(task) && b.matches(task),
+            Query::Or(a, b) => a.matches(task) || b.matches(task),
+            Query::Not(query) => !query.matches(task),
+            Query::Done => task.is_completed(),
+            Query::Recurring => task.recurrence.is_some(),
+            Query::Tag(tag) => task.tags.contains(tag),
+            Query::NoDueDate => task.due_date.is_none(),
+            Query::Due(comparison, span) => task.due_date.is_some_and(|due| {
+                match comparison {
+                    Comparison::Equal => span.start <= due && due < span.end,
+                    Comparison::NotEqual => due < span.start || span.end <= due,
+                    Comparison::Less => due < span.start,
+                    Comparison::LessOrEqual => due < span.end,
+                    Comparison::Greater => due >= span.end,
+                    Comparison::GreaterOrEqual => due >= span.start,
+                }
+            }),
+            Query::Priority(comparison, priority) => match comparison {
+                Comparison::Equal => task.priority == *priority,
+                Comparison::NotEqual => task.priority != *priority,
+                Comparison::Less => task.priority < *priority,
+                Comparison::LessOrEqual => task.priority <= *priority,
+                Comparison::Greater => task.priority > *priority,
+                Comparison::GreaterOrEqual => task.priority >= *priority,
+            },
+            Query::DescriptionContains(text) => task.description.to_lowercase().contains(text.as_str()),
+            Query::DescriptionEquals(text) => task.description.to_lowercase() == *text,
+        }
+    }
+
+    /// Returns the tasks in `tasks` that match, in their original order.
+    pub fn filter<'a>(&self, tasks: &'a [Task]) -> Vec<&'a Task> {
+        tasks.iter().filter(|task| self.matches(task)).collect()
+    }
+}
+
+/// Why a filter expression could not be parsed, and where.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseQueryError {
+    message: String,
+    /// 1-based character position the problem was found at.
+    column: usize,
+}
+
+impl ParseQueryError {
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for ParseQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at column {})", self.message, self.column)
+    }
+}
+
+impl Error for ParseQueryError {}
+
+/// Parses a filter expression relative to the current time in the local zone.
+///
+/// See [`parse_query_at`] for the syntax.
+pub fn parse_query(input: &str, settings: &DateSettings) -> Result<Query, ParseQueryError> {
+    parse_query_at(input, Utc::now(), Zone::Local, settings)
+}
+
+/// Parses a filter expression such as `due:<tomorrow and +work and not
+/// done`. Filters are combined with `and`, `or`, `not` and parentheses;
+/// filters next to each other must all match. A filter is one of:
+///
+/// - `+project`, `@context`, `#tag`, or `tag:`, `project:`, `context:`
+/// - `done`, `open`, `recurring`, or `status:done` / `status:open`
+/// - `due` compared with a day (`today`, `fri`, `2026-11-01`), an instant
+///   (`now`, `"in 2 hours"`) or `none`, e.g. `due:today`, `due<now`
+/// - `priority` compared with a level, e.g. `priority>=high`
+/// - `desc~invoice` (contains) or `desc="pay rent"` (whole description)
+///
+/// Comparisons are `:` or `=`, `!=`, `<`, `<=`, `>`, `>=`; `due:<x` is the
+/// same as `due<x`. Values with spaces go in double quotes. Dates are read
+/// relative to `now` in `zone`, with `settings`.
+pub fn parse_query_at(
+    input: &str,
+    now: DateTime<Utc>,
+    zone: Zone,
+    settings: &DateSettings,
+) -> Result<Query, ParseQueryError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(Query::All);
+    }
+    let mut parser = Parser { tokens, position: 0, end_column: input.chars().count() + 1, now, zone, settings };
+    let query = parser.parse_or()?;
+    match parser.tokens.get(parser.position) {
+        Some((column, Token::Close)) => Err(error(*column, "unexpected ')'")),
+        Some((column, _)) => Err(error(*column, "unexpected input")),
+        None => Ok(query),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    /// A word as typed, quotes included.
+    Word(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ParseQueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+    while let Some(&(i, c)) = chars.peek() {
+        let column = i + 1;
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push((column, Token::Open));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((column, Token::Close));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&(j, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    word.push(c);
+                    if c != '"' {
+                        continue;
+                    }
+                    // A quoted value runs to the closing quote, spaces and all.
+                    loop {
+                        match chars.next() {
+                            Some((_, '\\')) => {
+                                word.push('\\');
+                                if let Some((_, escaped)) = chars.next() {
+                                    word.push(escaped);
+                                }
+                            }
+                            Some((_, '"')) => {
+                                word.push('"');
+                                break;
+                            }
+                            Some((_, c)) => word.push(c),
+                            None => return Err(error(j + 1, "unterminated quote")),
+                        }
+                    }
+                }
+                tokens.push((column, Token::Word(word)));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    end_column: usize,
+    now: DateTime<Utc>,
+    zone: Zone,
+    settings: &'a DateSettings,
+}
+
+impl Parser<'_> {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.position), Some((_, Token::Word(word))) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Query, ParseQueryError> {
+        let mut query = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.expect_after("or")?;
+            query = Query::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, ParseQueryError> {
+        let mut query = self.parse_unary()?;
+        loop {
+            if self.peek_keyword("and") {
+                self.expect_after("and")?;
+            } else if self.peek_keyword("or") || !matches!(self.tokens.get(self.position), Some((_, Token::Word(_))) | Some((_, Token::Open))) {
+                return Ok(query);
+            }
+            query = Query::And(Box::new(query), Box::new(self.parse_unary()?));
+        }
+    }
+
+    /// Skips `keyword`, which must be followed by another filter.
+    fn expect_after(&mut self, keyword: &str) -> Result<(), ParseQueryError> {
+        self.position += 1;
+        match self.tokens.get(self.position) {
+            None => Err(error(self.end_column, &format!("expected a filter after '{}'", keyword))),
+            Some((column, Token::Close)) => Err(error(*column, &format!("expected a filter after '{}'", keyword))),
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, ParseQueryError> {
+        let (column, token) = match self.tokens.get(self.position) {
+            Some(token) => token.clone(),
+            None => return Err(error(self.end_column, "expected a filter")),
+        };
+        self.position += 1;
+        match token {
+            Token::Open => {
+                let query = self.parse_or()?;
+                match self.tokens.get(self.position) {
+                    Some((_, Token::Close)) => {
+                        self.position += 1;
+                        Ok(query)
+                    }
+                    Some((column, _)) => Err(error(*column, "expected ')'")),
+                    None => Err(error(column, "missing ')' for this '('")),
+                }
+            }
+            Token::Close => Err(error(column, "expected a filter before ')'")),
+            Token::Word(word) if word.eq_ignore_ascii_case("not") => Ok(Query::Not(Box::new(self.parse_unary()?))),
+            Token::Word(word) if word.eq_ignore_ascii_case("and") || word.eq_ignore_ascii_case("or") => {
+                Err(error(column, &format!("expected a filter before '{}'", word)))
+            }
+            Token::Word(word) => self.parse_filter(&word, column),
+        }
+    }
+
+    fn parse_filter(&self, word: &str, column: usize) -> Result<Query, ParseQueryError> {
+        if word.starts_with(['+', '@', '#']) {
+            return word
+                .parse()
+                .map(Query::Tag)
+                .map_err(|e: crate::tags::ParseTagError| error(column, &e.to_string()));
+        }
+        match word.to_lowercase().as_str() {
+            "done" | "completed" => return Ok(Query::Done),
+            "open" => return Ok(Query::Not(Box::new(Query::Done))),
+            "recurring" => return Ok(Query::Recurring),
+            _ => {}
+        }
+
+        let field_len = word.find(|c: char| !(c.is_ascii_alphabetic() || c == '_')).unwrap_or(word.len());
+        let (field, rest) = word.split_at(field_len);
+        let (colon, rest) = match rest.strip_prefix(':') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        let operators = [
+            ("<=", Operator::Compare(Comparison::LessOrEqual)),
+            (">=", Operator::Compare(Comparison::GreaterOrEqual)),
+            ("!=", Operator::Compare(Comparison::NotEqual)),
+            ("<", Operator::Compare(Comparison::Less)),
+            (">", Operator::Compare(Comparison::Greater)),
+            ("=", Operator::Compare(Comparison::Equal)),
+            ("~", Operator::Like),
+        ];
+        let (operator, value) = match operators.iter().find(|(symbol, _)| rest.starts_with(symbol)) {
+            Some((symbol, operator)) => (*operator, &rest[symbol.len()..]),
+            None if colon => (Operator::Colon, rest),
+            None => {
+                return Err(error(
+                    column,
+                    &format!(
+                        "unknown filter '{}'; expected a field such as due:, priority: or desc:, \
+                         a +project, @context or #tag, or done, open or recurring",
+                        word
+                    ),
+                ))
+            }
+        };
+        let value_column = column + word.chars().count() - value.chars().count();
+        let value = unquote(value).ok_or_else(|| error(value_column, "badly quoted value"))?;
+        if value.is_empty() {
+            return Err(error(value_column, &format!("missing value for '{}'", field)));
+        }
+
+        let field = field.to_lowercase();
+        match field.as_str() {
+            "due" => self.due_filter(operator, &value, value_column),
+            "priority" | "pri" => {
+                let priority = value
+                    .parse()
+                    .map_err(|e: crate::task::ParsePriorityError| error(value_column, &e.to_string()))?;
+                match operator.comparison() {
+                    Some(comparison) => Ok(Query::Priority(comparison, priority)),
+                    None => Err(error(column, "'~' only works with desc")),
+                }
+            }
+            "desc" | "description" => {
+                let value = value.to_lowercase();
+                match operator {
+                    Operator::Colon | Operator::Like => Ok(Query::DescriptionContains(value)),
+                    Operator::Compare(Comparison::Equal) => Ok(Query::DescriptionEquals(value)),
+                    Operator::Compare(Comparison::NotEqual) => Ok(negate(Query::DescriptionEquals(value))),
+                    Operator::Compare(_) => Err(error(column, "desc can only be compared with :, =, != or ~")),
+                }
+            }
+            "tag" | "project" | "context" => {
+                let tag = match (field.as_str(), value.starts_with(['+', '@', '#'])) {
+                    (_, true) => value.parse(),
+                    ("project", false) => format!("+{}", value).parse(),
+                    ("context", false) => format!("@{}", value).parse(),
+                    _ => format!("#{}", value).parse(),
+                };
+                let tag: Tag = tag.map_err(|e: crate::tags::ParseTagError| error(value_column, &e.to_string()))?;
+                if field == "project" && tag.kind != TagKind::Project || field == "context" && tag.kind != TagKind::Context {
+                    return Err(error(value_column, &format!("'{}' is not a {}", tag, field)));
+                }
+                match operator.comparison() {
+                    Some(Comparison::Equal) => Ok(Query::Tag(tag)),
+                    Some(Comparison::NotEqual) => Ok(negate(Query::Tag(tag))),
+                    _ => Err(error(column, &format!("{} can only be compared with :, = or !=", field))),
+                }
+            }
+            "status" => {
+                let done = match value.to_lowercase().as_str() {
+                    "done" | "completed" => Query::Done,
+                    "open" => negate(Query::Done),
+                    _ => return Err(error(value_column, "status must be 'done' or 'open'")),
+                };
+                match operator.comparison() {
+                    Some(Comparison::Equal) => Ok(done),
+                    Some(Comparison::NotEqual) => Ok(negate(done)),
+                    _ => Err(error(column, "status can only be compared with :, = or !=")),
+                }
+            }
+            "" => Err(error(column, &format!("expected a field name before '{}'", word))),
+            _ => Err(error(
+                column,
+                &format!("unknown field '{}'; use due, priority, desc, tag, project, context or status", field),
+            )),
+        }
+    }
+
+    fn due_filter(&self, operator: Operator, value: &str, column: usize) -> Result<Query, ParseQueryError> {
+        let comparison = operator.comparison().ok_or_else(|| error(column, "'~' only works with desc"))?;
+        if value.eq_ignore_ascii_case("none") {
+            return match comparison {
+                Comparison::Equal => Ok(Query::NoDueDate),
+                Comparison::NotEqual => Ok(negate(Query::NoDueDate)),
+                _ => Err(error(column, "'none' can only be compared with :, = or !=")),
+            };
+        }
+        let span = self.parse_span(value).ok_or_else(|| error(column, &format!("could not understand date '{}'", value)))?;
+        Ok(Query::Due(comparison, span))
+    }
+
+    /// Reads a day expression as the whole day, and anything else that
+    /// names a time as that second.
+    fn parse_span(&self, value: &str) -> Option<Span> {
+        if let Some(day) = dates::parse_day_at(value, self.now, self.zone, self.settings) {
+            return Some(Span { start: self.midnight(day)?, end: self.midnight(day.succ_opt()?)? });
+        }
+        let parsed = dates::parse_due_date_at(value, self.now, self.zone, self.settings).ok()?;
+        let start = parsed.resolution.choose(DstChoice::Earlier);
+        Some(Span { start, end: start + Duration::seconds(1) })
+    }
+
+    fn midnight(&self, day: NaiveDate) -> Option<DateTime<Utc>> {
+        Some(self.zone.resolve(day.and_time(NaiveTime::MIN)).choose(DstChoice::Later))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    /// A bare `:`, which means equality except for descriptions.
+    Colon,
+    Compare(Comparison),
+    Like,
+}
+
+impl Operator {
+    fn comparison(self) -> Option<Comparison> {
+        match self {
+            Operator::Colon => Some(Comparison::Equal),
+            Operator::Compare(comparison) => Some(comparison),
+            Operator::Like => None,
+        }
+    }
+}
+
+fn negate(query: Query) -> Query {
+    Query::Not(Box::new(query))
+}
+
+/// Removes surrounding double quotes and backslash escapes, if quoted.
+fn unquote(value: &str) -> Option<String> {
+    let inner = match value.strip_prefix('"') {
+        Some(rest) => rest.strip_suffix('"')?,
+        None if value.contains('"') => return None,
+        None => return Some(value.to_string()),
+    };
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+fn error(column: usize, message: &str) -> ParseQueryError {
+    ParseQueryError { message: message.to_string(), column }
+}
+
+/// A filter saved under a name, such as `today`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    pub name: String,
+    pub query: String,
+}
+
+impl View {
+    pub fn new(name: &str, query: &str) -> View {
+        View { name: name.to_string(), query: query.to_string() }
+    }
+}
+
+/// The views that exist without being saved: `today`, `overdue` and
+/// `this-week` (from today to the end of the week).
+pub fn builtin_views() -> Vec<View> {
+    vec![
+        View::new("today", "open and due:today"),
+        View::new("overdue", "open and due<now"),
+        View::new("this-week", "open and due>=today and due<=eow"),
+    ]
+}
+
+/// Combines the built-in views with `saved` ones; a saved view replaces a
+/// built-in one of the same name.
+pub fn all_views(saved: &[View]) -> Vec<View> {
+    let mut views: Vec<View> =
+        builtin_views().into_iter().filter(|view| !saved.iter().any(|s| s.name == view.name)).collect();
+    views.extend(saved.iter().cloned());
+    views
+}
+
+/// Reads saved views, one `name = query` per line. Blank lines and lines
+/// starting with `#` are ignored.
+pub fn read_views<R: BufRead>(reader: R) -> io::Result<Vec<View>> {
+    let mut views = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=') {
+            Some((name, query)) if is_view_name(name.trim()) => views.push(View::new(name.trim(), query.trim())),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: expected 'name = query'", i + 1),
+                ))
+            }
+        }
+    }
+    Ok(views)
+}
+
+pub fn write_views<W: Write>(mut writer: W, views: &[View]) -> io::Result<()> {
+    for view in views {
+        writeln!(writer, "{} = {}", view.name, view.query)?;
+    }
+    writer.flush()
+}
+
+/// Where views are saved for the tasks stored at `tasks_path`: `views.txt`
+/// in the same directory.
+pub fn views_path(tasks_path: &Path) -> PathBuf {
+    tasks_path.with_file_name("views.txt")
+}
+
+/// Reads the views saved at `path`; a missing file means none are saved.
+pub fn load_views(path: &Path) -> io::Result<Vec<View>> {
+    match File::open(path) {
+        Ok(file) => read_views(BufReader::new(file)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn save_views(path: &Path, views: &[View]) -> io::Result<()> {
+    if views.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    write_views(BufWriter::new(File::create(path)?), views)
+}
+
+/// View names are single words of letters, digits, `-` and `_`.
+pub fn is_view_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::TaskId;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn query(input: &str) -> Result<Query, ParseQueryError> {
+        // Wednesday 14 October 2026, 15:30 in Berlin.
+        let zone = Zone::parse("Europe/Berlin").unwrap();
+        parse_query_at(input, utc("2026-10-14T13:30:00Z"), zone, &DateSettings::default())
+    }
+
+    fn matching(input: &str, tasks: &[Task]) -> Vec<String> {
+        let query = query(input).unwrap();
+        query.filter(tasks).into_iter().map(|task| task.description.clone()).collect()
+    }
+
+    fn tasks() -> Vec<Task> {
+        let task = |description: &str, due: Option<&str>| Task::new(TaskId::generate(), description.to_string(), due.map(utc));
+        let mut done = task("Old invoice +work", Some("2026-10-13T08:00:00Z"));
+        done.complete(utc("2026-10-13T09:00:00Z"));
+        vec![
+            task("Send invoice +work", Some("2026-10-14T20:00:00Z")),
+            Task { priority: Priority::Urgent, ..task("Fix roof @home", Some("2026-10-12T08:00:00Z")) },
+            task("Plan trip +holiday", Some("2026-10-16T08:00:00Z")),
+            task("Read book", None),
+            done,
+        ]
+    }
+
+    #[test]
+    fn evaluates_filters() {
+        let tasks = tasks();
+        assert_eq!(matching("due:<tomorrow and +work and not done", &tasks), ["Send invoice +work"]);
+        assert_eq!(matching("priority>=high", &tasks), ["Fix roof @home"]);
+        assert_eq!(matching("desc~\"INVOICE\"", &tasks), ["Send invoice +work", "Old invoice +work"]);
+        assert_eq!(matching("due:none or (+holiday due:fri)", &tasks), ["Plan trip +holiday", "Read book"]);
+        assert_eq!(matching("open due<now", &tasks), ["Fix roof @home"]);
+        assert_eq!(matching("project:work status:done", &tasks), ["Old invoice +work"]);
+        assert_eq!(matching("desc=\"read book\"", &tasks), ["Read book"]);
+        assert_eq!(matching("", &tasks).len(), tasks.len());
+    }
+
+    #[test]
+    fn builtin_views_parse() {
+        let tasks = tasks();
+        let view = |name: &str| builtin_views().into_iter().find(|view| view.name == name).unwrap().query;
+        assert_eq!(matching(&view("today"), &tasks), ["Send invoice +work"]);
+        assert_eq!(matching(&view("overdue"), &tasks), ["Fix roof @home"]);
+        assert_eq!(matching(&view("this-week"), &tasks), ["Send invoice +work", "Plan trip +holiday"]);
+    }
+
+    #[test]
+    fn reports_where_syntax_is_wrong() {
+        let column = |input: &str| query(input).unwrap_err().column();
+        assert_eq!(column("+work and"), 10);
+        assert_eq!(column("(done"), 1);
+        assert_eq!(column("done )"), 6);
+        assert_eq!(column("due<blursday"), 5);
+        assert_eq!(column("colour:red"), 1);
+        assert_eq!(column("desc~\"unterminated"), 6);
+        assert_eq!(column("or done"), 1);
+        assert!(query("priority>=important").unwrap_err().to_string().contains("unknown priority"));
+    }
+
+    #[test]
+    fn views_round_trip() {
+        let views = vec![View::new("work", "+work and open"), View::new("soon", "due<\"in 2 days\"")];
+        let mut buffer = Vec::new();
+        write_views(&mut buffer, &views).unwrap();
+        assert_eq!(read_views(buffer.as_slice()).unwrap(), views);
+        assert!(read_views("no equals sign\n".as_bytes()).is_err());
+    }
+}
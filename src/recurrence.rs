@@ -0,0 +1,642 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use chrono::{Datelike, DateTime, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+
+use crate::dates::{self, DateSettings, DstChoice, Resolution, Zone};
+
+/// Gives up looking for the next occurrence after this many periods, so a
+/// rule that can never match (day 30 of every February) does not loop.
+const MAX_PERIODS: i64 = 1000;
+
+const RRULE_DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A weekday in a `BYDAY` list, optionally numbered within the month:
+/// `2TU` is the second Tuesday and `-1FR` the last Friday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+/// When a task repeats, following the `RRULE` semantics of RFC 5545. The
+/// task's due date plays the role of `DTSTART`: it fixes the time of day
+/// and, unless the rule says otherwise, the weekday or day of the month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// Repeat every `interval` days, weeks, months or years.
+    pub interval: u32,
+    /// Weekdays to repeat on; empty means the weekday of the due date.
+    pub by_day: Vec<ByDay>,
+    /// Days of the month to repeat on, negative ones counting back from
+    /// the last day; empty means the day of the due date.
+    pub by_month_day: Vec<i32>,
+    /// No occurrence falls after this instant.
+    pub until: Option<DateTime<Utc>>,
+    /// Occurrences left, including the current one.
+    pub count: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseRecurrenceError {
+    message: String,
+}
+
+impl fmt::Display for ParseRecurrenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ParseRecurrenceError {}
+
+impl Recurrence {
+    pub fn new(frequency: Frequency) -> Recurrence {
+        Recurrence { frequency, interval: 1, by_day: Vec::new(), by_month_day: Vec::new(), until: None, count: None }
+    }
+
+    /// Returns the first occurrence after `current`, which is expected to
+    /// be an occurrence itself, reading wall-clock times in `zone`. `count`
+    /// is not consulted; callers track how many occurrences are left.
+    pub fn next_after(&self, current: DateTime<Utc>, zone: Zone) -> Option<DateTime<Utc>> {
+        let start = zone.wall_clock(current);
+        let interval = i64::from(self.interval.max(1));
+        for period in 0..MAX_PERIODS {
+            let next = self
+                .dates_in_period(start.date(), period * interval)?
+                .into_iter()
+                .find(|&date| date > start.date());
+            if let Some(date) = next {
+                let instant = resolve(zone, date.and_time(start.time()));
+                return match self.until {
+                    Some(until) if instant > until => None,
+                    _ => Some(instant),
+                };
+            }
+        }
+        None
+    }
+
+    /// Describes the rule in words, e.g. "every 2 weeks on Mon, Wed".
+    pub fn describe(&self) -> String {
+        let unit = match self.frequency {
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Monthly => "month",
+            Frequency::Yearly => "year",
+        };
+        let mut text = match self.interval {
+            1 => format!("every {}", unit),
+            n => format!("every {} {}s", n, unit),
+        };
+
+        let mut days: Vec<String> = self
+            .by_month_day
+            .iter()
+            .map(|&day| match day {
+                -1 => "the last day".to_string(),
+                day if day < 0 => format!("day {} from the end", -day),
+                day => format!("day {}", day),
+            })
+            .collect();
+        days.extend(self.by_day.iter().map(|by_day| match by_day.ordinal {
+            Some(-1) => format!("the last {}", by_day.weekday),
+            Some(n) if n < 0 => format!("the {} {} from the end", ordinal_word(-n), by_day.weekday),
+            Some(n) => format!("the {} {}", ordinal_word(n), by_day.weekday),
+            None => by_day.weekday.to_string(),
+        }));
+        if !days.is_empty() {
+            text.push_str(" on ");
+            text.push_str(&days.join(", "));
+        }
+
+        if let Some(count) = self.count {
+            text.push_str(&format!(", {} time{} left", count, if count == 1 { "" } else { "s" }));
+        }
+        if let Some(until) = self.until {
+            text.push_str(&format!(" until {}", until.with_timezone(&Local)));
+        }
+        text
+    }
+
+    /// The dates of the period `offset` days, weeks, months or years after
+    /// the one containing `start`, in order.
+    fn dates_in_period(&self, start: NaiveDate, offset: i64) -> Option<Vec<NaiveDate>> {
+        let mut dates = match self.frequency {
+            Frequency::Daily => {
+                let date = start.checked_add_signed(Duration::days(offset))?;
+                let first = date.with_day(1)?;
+                let weekday_ok = self.by_day.is_empty() || self.by_day.iter().any(|d| d.weekday == date.weekday());
+                let day_ok = self.by_month_day.is_empty()
+                    || self.by_month_day.iter().any(|&n| month_day(first, n) == Some(date));
+                if weekday_ok && day_ok {
+                    vec![date]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let days_since_monday = i64::from(start.weekday().num_days_from_monday());
+                let monday = start.checked_add_signed(Duration::days(offset * 7 - days_since_monday))?;
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|d| d.weekday).collect()
+                };
+                weekdays
+                    .into_iter()
+                    .map(|weekday| monday + Duration::days(weekday.num_days_from_monday().into()))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let first = start.with_day(1)?.checked_add_months(Months::new(u32::try_from(offset).ok()?))?;
+                self.days_in_month(first, start)
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(i32::try_from(offset).ok()?)?;
+                NaiveDate::from_ymd_opt(year, start.month(), start.day()).into_iter().collect()
+            }
+        };
+        dates.sort();
+        dates.dedup();
+        Some(dates)
+    }
+
+    /// The days in the month starting at `first` that a monthly rule picks.
+    /// `BYDAY` and `BYMONTHDAY` together select the days matching both.
+    fn days_in_month(&self, first: NaiveDate, start: NaiveDate) -> Vec<NaiveDate> {
+        let by_month_day: Vec<NaiveDate> = self.by_month_day.iter().filter_map(|&n| month_day(first, n)).collect();
+        let by_day: Vec<NaiveDate> = self.by_day.iter().flat_map(|&by_day| weekdays_in_month(first, by_day)).collect();
+        match (self.by_month_day.is_empty(), self.by_day.is_empty()) {
+            (true, true) => month_day(first, start.day() as i32).into_iter().collect(),
+            (false, true) => by_month_day,
+            (true, false) => by_day,
+            (false, false) => by_month_day.into_iter().filter(|date| by_day.contains(date)).collect(),
+        }
+    }
+
+    fn validate(self) -> Result<Recurrence, ParseRecurrenceError> {
+        let problem = if self.interval == 0 {
+            Some("the interval must be at least 1")
+        } else if self.count == Some(0) {
+            Some("the count must be at least 1")
+        } else if self.count.is_some() && self.until.is_some() {
+            Some("a rule cannot have both a count and an end date")
+        } else if self.by_month_day.iter().any(|&day| day == 0 || !(-31..=31).contains(&day)) {
+            Some("days of the month must be between 1 and 31, or -1 and -31 from the end")
+        } else if !self.by_month_day.is_empty() && !matches!(self.frequency, Frequency::Daily | Frequency::Monthly) {
+            Some("days of the month are only supported for daily and monthly rules")
+        } else if self.frequency == Frequency::Yearly && !self.by_day.is_empty() {
+            Some("weekdays are not supported for yearly rules")
+        } else if self.by_day.iter().any(|d| d.ordinal.is_some()) && self.frequency != Frequency::Monthly {
+            Some("numbered weekdays such as 'last friday' need a monthly rule")
+        } else if self.by_day.iter().filter_map(|d| d.ordinal).any(|n| n == 0 || !(-5..=5).contains(&n)) {
+            Some("weekday numbers must be between 1 and 5, or -1 and -5 from the end")
+        } else {
+            None
+        };
+        match problem {
+            Some(problem) => Err(ParseRecurrenceError { message: problem.to_string() }),
+            None => Ok(self),
+        }
+    }
+}
+
+/// Writes the rule as an RFC 5545 `RRULE` value, e.g. `FREQ=MONTHLY;BYDAY=-1FR`.
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|d| format!("{}{}", d.ordinal.map(|n| n.to_string()).unwrap_or_default(), weekday_code(d.weekday)))
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days: Vec<String> = self.by_month_day.iter().map(i32::to_string).collect();
+            write!(f, ";BYMONTHDAY={}", days.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format(RRULE_DATE_FORMAT))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses an RFC 5545 `RRULE` value (without the `RRULE:` prefix).
+impl FromStr for Recurrence {
+    type Err = ParseRecurrenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: String| ParseRecurrenceError { message };
+        let mut frequency = None;
+        let mut rule = Recurrence::new(Frequency::Daily);
+
+        for part in s.trim().split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("invalid RRULE part '{}'", part)))?;
+            let bad_value = || invalid(format!("invalid {} '{}'", key, value));
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(invalid(format!("unsupported FREQ '{}'", value))),
+                    })
+                }
+                "INTERVAL" => rule.interval = value.parse().map_err(|_| bad_value())?,
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| bad_value())?),
+                "UNTIL" => rule.until = Some(parse_rrule_date(value).ok_or_else(bad_value)?),
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(|day| parse_by_day(day).ok_or_else(bad_value))
+                        .collect::<Result<_, _>>()?
+                }
+                "BYMONTHDAY" => {
+                    rule.by_month_day = value
+                        .split(',')
+                        .map(|day| day.parse().map_err(|_| bad_value()))
+                        .collect::<Result<_, _>>()?
+                }
+                // Weeks always start on Monday here, as they do by default.
+                "WKST" if value.eq_ignore_ascii_case("MO") => {}
+                _ => return Err(invalid(format!("unsupported RRULE part '{}'", part))),
+            }
+        }
+
+        rule.frequency = frequency.ok_or_else(|| invalid("RRULE is missing FREQ".to_string()))?;
+        rule.validate()
+    }
+}
+
+/// Parses a recurrence relative to the current time in the local zone.
+///
+/// See [`parse_recurrence_at`] for the accepted syntax.
+pub fn parse_recurrence(input: &str, settings: &DateSettings) -> Result<Recurrence, ParseRecurrenceError> {
+    parse_recurrence_at(input, Utc::now(), Zone::Local, settings)
+}
+
+/// Parses either an `RRULE` value (`FREQ=WEEKLY;BYDAY=MO,WE`, optionally
+/// prefixed with `RRULE:`) or a phrase such as `daily`, `every 2 weeks on
+/// mon, wed`, `every weekday`, `monthly on the 15th`, `monthly on the last
+/// friday` or `yearly`, optionally followed by `until DATE` or `N times`.
+/// `now`, `zone` and `settings` are used to read the `until` date.
+pub fn parse_recurrence_at(
+    input: &str,
+    now: DateTime<Utc>,
+    zone: Zone,
+    settings: &DateSettings,
+) -> Result<Recurrence, ParseRecurrenceError> {
+    let text = input.trim();
+    let upper = text.to_ascii_uppercase();
+    if let Some(rule) = upper.strip_prefix("RRULE:") {
+        return rule.parse();
+    }
+    if upper.starts_with("FREQ=") {
+        return upper.parse();
+    }
+
+    parse_phrase(text, now, zone, settings)?
+        .ok_or_else(|| ParseRecurrenceError {
+            message: format!(
+                "could not understand recurrence '{}'; try 'daily', 'every 2 weeks on mon, wed', \
+                 'monthly on the last friday' or an RRULE such as 'FREQ=WEEKLY;BYDAY=MO'",
+                text
+            ),
+        })?
+        .validate()
+}
+
+/// Parses the plain-English form; `Ok(None)` means it was not understood.
+fn parse_phrase(
+    text: &str,
+    now: DateTime<Utc>,
+    zone: Zone,
+    settings: &DateSettings,
+) -> Result<Option<Recurrence>, ParseRecurrenceError> {
+    let text = text.to_lowercase().replace(',', " ");
+    let (text, until) = match text.split_once(" until ") {
+        Some((rule, until)) => (rule.to_string(), Some(parse_until(until.trim(), now, zone, settings)?)),
+        None => (text, None),
+    };
+    let mut tokens: Vec<&str> = text
+        .split_whitespace()
+        .filter(|token| !matches!(*token, "on" | "and" | "the" | "of" | "for"))
+        .collect();
+
+    let mut count = None;
+    if let [rest @ .., n, "times" | "time"] = tokens.as_slice() {
+        match n.parse() {
+            Ok(n) => count = Some(n),
+            Err(_) => return Ok(None),
+        }
+        tokens = rest.to_vec();
+    }
+
+    let (mut rule, rest) = match frequency_prefix(&tokens) {
+        Some(prefix) => prefix,
+        None => return Ok(None),
+    };
+    rule.count = count;
+    rule.until = until;
+
+    let mut rest = rest.iter().copied().peekable();
+    while let Some(token) = rest.next() {
+        if let Some(weekday) = dates::parse_weekday(token) {
+            rule.by_day.push(ByDay { ordinal: None, weekday });
+        } else if matches!(token, "weekday" | "weekdays") {
+            let weekdays = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+            rule.by_day.extend(weekdays.iter().map(|&weekday| ByDay { ordinal: None, weekday }));
+        } else if rule.frequency != Frequency::Monthly {
+            return Ok(None);
+        } else if token == "month" {
+            // "the last friday of the month"
+        } else if token == "day" {
+            match rest.next().and_then(parse_day_number) {
+                Some(day) => rule.by_month_day.push(day),
+                None => return Ok(None),
+            }
+        } else if let Some(ordinal) = parse_ordinal(token) {
+            match rest.next() {
+                Some("day") => rule.by_month_day.push(ordinal),
+                Some(day) => match dates::parse_weekday(day) {
+                    Some(weekday) => rule.by_day.push(ByDay { ordinal: Some(ordinal), weekday }),
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            }
+        } else if let Some(day) = parse_day_number(token) {
+            rule.by_month_day.push(day);
+        } else {
+            return Ok(None);
+        }
+    }
+    Ok(Some(rule))
+}
+
+/// Parses how often a phrase repeats ("daily", "every 2 weeks", "every
+/// other month"), returning the rule and the words after it.
+fn frequency_prefix<'a, 'b>(tokens: &'a [&'b str]) -> Option<(Recurrence, &'a [&'b str])> {
+    let (frequency, interval, rest) = match tokens {
+        ["daily", rest @ ..] => (Frequency::Daily, 1, rest),
+        ["weekly", rest @ ..] => (Frequency::Weekly, 1, rest),
+        ["monthly", rest @ ..] => (Frequency::Monthly, 1, rest),
+        ["yearly" | "annually", rest @ ..] => (Frequency::Yearly, 1, rest),
+        ["every" | "each", "other", unit, rest @ ..] => (unit_frequency(unit)?, 2, rest),
+        ["every" | "each", n, unit, rest @ ..] if n.parse::<u32>().is_ok() => {
+            (unit_frequency(unit)?, n.parse().ok()?, rest)
+        }
+        ["every" | "each", unit, rest @ ..] if unit_frequency(unit).is_some() => (unit_frequency(unit)?, 1, rest),
+        // "every monday", "every weekday": the weekdays follow.
+        ["every" | "each", rest @ ..] if !rest.is_empty() => (Frequency::Weekly, 1, rest),
+        _ => return None,
+    };
+    Some((Recurrence { interval, ..Recurrence::new(frequency) }, rest))
+}
+
+fn unit_frequency(unit: &str) -> Option<Frequency> {
+    match unit {
+        "day" | "days" => Some(Frequency::Daily),
+        "week" | "weeks" => Some(Frequency::Weekly),
+        "month" | "months" => Some(Frequency::Monthly),
+        "year" | "years" => Some(Frequency::Yearly),
+        _ => None,
+    }
+}
+
+/// Parses "first" to "fifth" (or "1st" to "5th") and "last".
+fn parse_ordinal(token: &str) -> Option<i32> {
+    match token {
+        "first" | "1st" => Some(1),
+        "second" | "2nd" => Some(2),
+        "third" | "3rd" => Some(3),
+        "fourth" | "4th" => Some(4),
+        "fifth" | "5th" => Some(5),
+        "last" => Some(-1),
+        _ => None,
+    }
+}
+
+/// Parses a day of the month written as "15" or "15th".
+fn parse_day_number(token: &str) -> Option<i32> {
+    let digits = token.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &token[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Reads an end date; a bare date includes the whole day.
+fn parse_until(
+    text: &str,
+    now: DateTime<Utc>,
+    zone: Zone,
+    settings: &DateSettings,
+) -> Result<DateTime<Utc>, ParseRecurrenceError> {
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        let end_of_day = NaiveTime::from_hms_opt(23, 59, 59).unwrap_or(NaiveTime::MIN);
+        return Ok(resolve(zone, date.and_time(end_of_day)));
+    }
+    dates::parse_due_date_at(text, now, zone, settings)
+        .map(|parsed| parsed.resolution.choose(DstChoice::Later))
+        .map_err(|e| ParseRecurrenceError { message: e.to_string() })
+}
+
+fn parse_rrule_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = NaiveDateTime::parse_from_str(value, RRULE_DATE_FORMAT) {
+        return Some(Utc.from_utc_datetime(&date));
+    }
+    // A date alone includes the whole day.
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+    Some(Utc.from_utc_datetime(&date.and_hms_opt(23, 59, 59)?))
+}
+
+fn parse_by_day(text: &str) -> Option<ByDay> {
+    let text = text.trim().to_ascii_uppercase();
+    let split = text.len().checked_sub(2)?;
+    let (ordinal, code) = text.split_at(split);
+    let weekday = match code {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => Some(ordinal.strip_prefix('+').unwrap_or(ordinal).parse().ok()?),
+    };
+    Some(ByDay { ordinal, weekday })
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn ordinal_word(n: i32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+/// Returns day `n` of the month starting at `first`, counting back from
+/// the last day when `n` is negative, or `None` if the month is too short.
+fn month_day(first: NaiveDate, n: i32) -> Option<NaiveDate> {
+    if n > 0 {
+        return first.with_day(n as u32);
+    }
+    let last = first.checked_add_months(Months::new(1))?.pred_opt()?;
+    let day = last.day() as i32 + n + 1;
+    if day < 1 {
+        return None;
+    }
+    first.with_day(day as u32)
+}
+
+fn weekdays_in_month(first: NaiveDate, by_day: ByDay) -> Vec<NaiveDate> {
+    let days: Vec<NaiveDate> = first
+        .iter_days()
+        .take_while(|date| date.month() == first.month())
+        .filter(|date| date.weekday() == by_day.weekday)
+        .collect();
+    match by_day.ordinal {
+        None => days,
+        Some(n) if n > 0 => days.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => days.len().checked_sub((-n) as usize).map(|i| days[i]).into_iter().collect(),
+    }
+}
+
+/// Maps an occurrence's wall-clock time to an instant as RFC 5545 does:
+/// times skipped by a DST change use the offset from before the change,
+/// and repeated times use their first occurrence.
+fn resolve(zone: Zone, naive: NaiveDateTime) -> DateTime<Utc> {
+    match zone.resolve(naive) {
+        skipped @ Resolution::Skipped { .. } => skipped.choose(DstChoice::Later),
+        resolution => resolution.choose(DstChoice::Earlier),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn berlin() -> Zone {
+        Zone::parse("Europe/Berlin").unwrap()
+    }
+
+    /// The first `n` occurrences starting with `start`.
+    fn occurrences(rule: &str, start: &str, n: usize) -> Vec<DateTime<Utc>> {
+        let rule = parse_recurrence_at(rule, utc(start), berlin(), &DateSettings::default()).unwrap();
+        let mut dates = vec![utc(start)];
+        while dates.len() < n {
+            match rule.next_after(*dates.last().unwrap(), berlin()) {
+                Some(next) => dates.push(next),
+                None => break,
+            }
+        }
+        dates
+    }
+
+    #[test]
+    fn phrases_match_rrules() {
+        let now = utc("2026-10-14T13:30:00Z");
+        let cases = [
+            ("daily", "FREQ=DAILY"),
+            ("every 3 days", "FREQ=DAILY;INTERVAL=3"),
+            ("every other week on mon, wed", "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE"),
+            ("every weekday", "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR"),
+            ("every friday", "FREQ=WEEKLY;BYDAY=FR"),
+            ("monthly on the 15th", "FREQ=MONTHLY;BYMONTHDAY=15"),
+            ("monthly on the last friday", "FREQ=MONTHLY;BYDAY=-1FR"),
+            ("every month on the last day", "FREQ=MONTHLY;BYMONTHDAY=-1"),
+            ("yearly 3 times", "FREQ=YEARLY;COUNT=3"),
+            ("every week until 2026-12-31", "FREQ=WEEKLY;UNTIL=20261231T225959Z"),
+        ];
+        for (phrase, rrule) in cases {
+            let rule = parse_recurrence_at(phrase, now, berlin(), &DateSettings::default()).unwrap();
+            assert_eq!(rule.to_string(), rrule, "{}", phrase);
+            assert_eq!(rrule.parse::<Recurrence>().unwrap(), rule, "{}", rrule);
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        let now = utc("2026-10-14T13:30:00Z");
+        for input in ["sometimes", "every blue moon", "weekly on the 2nd", "FREQ=HOURLY", "FREQ=DAILY;COUNT=2;UNTIL=20261231", "INTERVAL=2"] {
+            assert!(parse_recurrence_at(input, now, berlin(), &DateSettings::default()).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn expands_weekly_and_monthly_rules() {
+        // Wednesday 14 October 2026, 09:00 in Berlin.
+        assert_eq!(
+            occurrences("every 2 weeks on mon, wed", "2026-10-14T07:00:00Z", 4),
+            vec![utc("2026-10-14T07:00:00Z"), utc("2026-10-26T08:00:00Z"), utc("2026-10-28T08:00:00Z"), utc("2026-11-09T08:00:00Z")]
+        );
+        assert_eq!(
+            occurrences("monthly on the last friday", "2026-10-30T07:00:00Z", 3),
+            vec![utc("2026-10-30T07:00:00Z"), utc("2026-11-27T07:00:00Z"), utc("2026-12-25T07:00:00Z")]
+        );
+        // Months without a 31st are skipped, as RFC 5545 requires.
+        assert_eq!(
+            occurrences("monthly", "2027-01-31T08:00:00Z", 3),
+            vec![utc("2027-01-31T08:00:00Z"), utc("2027-03-31T07:00:00Z"), utc("2027-05-31T07:00:00Z")]
+        );
+    }
+
+    #[test]
+    fn stops_at_until() {
+        assert_eq!(occurrences("daily until 2026-10-16", "2026-10-14T07:00:00Z", 10).len(), 3);
+        assert_eq!(occurrences("yearly", "2028-02-29T08:00:00Z", 2)[1], utc("2032-02-29T08:00:00Z"));
+    }
+}
@@ -0,0 +1,232 @@
+use std::io::{self, Write};
+use chrono::{DateTime, Duration, Utc};
+
+use crate::dates;
+use crate::task::{self, Priority, Task};
+use crate::view;
+
+/// Lead times given to new tasks unless the caller chooses others: a single
+/// reminder at the due time.
+pub fn default_lead_times() -> Vec<Duration> {
+    vec![Duration::zero()]
+}
+
+/// Where a task's reminder stands. Kept with the task so reminders are not
+/// repeated after a restart.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReminderState {
+    /// Reminders due at or before this instant have already fired.
+    pub fired_through: Option<DateTime<Utc>>,
+    /// A reminder has fired and has not been acknowledged or snoozed yet.
+    pub pending: bool,
+    /// Fire again at this instant.
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+impl ReminderState {
+    pub fn acknowledge(&mut self) {
+        self.pending = false;
+        self.snoozed_until = None;
+    }
+
+    pub fn snooze(&mut self, until: DateTime<Utc>) {
+        self.pending = false;
+        self.snoozed_until = Some(until);
+    }
+}
+
+/// Returns every instant at which the task's lead times put a reminder,
+/// earliest first, whether or not it has fired yet.
+pub fn reminder_times(task: &Task) -> Vec<DateTime<Utc>> {
+    let mut times: Vec<DateTime<Utc>> = match task.due_date {
+        Some(due) => task.lead_times.iter().filter_map(|&lead_time| due.checked_sub_signed(lead_time)).collect(),
+        None => Vec::new(),
+    };
+    times.sort();
+    times.dedup();
+    times
+}
+
+/// Returns the reminders of `task` that have not fired yet, including a
+/// snoozed one, earliest first. Completed tasks have none.
+pub fn upcoming_times(task: &Task) -> Vec<DateTime<Utc>> {
+    if task.is_completed() {
+        return Vec::new();
+    }
+    let state = &task.reminder;
+    let mut times: Vec<DateTime<Utc>> = reminder_times(task)
+        .into_iter()
+        .filter(|&at| state.fired_through.is_none_or(|fired| at > fired))
+        .chain(state.snoozed_until)
+        .collect();
+    times.sort();
+    times.dedup();
+    times
+}
+
+/// Returns when the task's next reminder fires, or `None` if it has
+/// nothing left to remind about.
+pub fn next_reminder(task: &Task) -> Option<DateTime<Utc>> {
+    upcoming_times(task).first().copied()
+}
+
+/// Returns every reminder still to fire across `tasks`, in firing order.
+pub fn upcoming(tasks: &[Task]) -> Vec<(DateTime<Utc>, &Task)> {
+    let mut upcoming: Vec<(DateTime<Utc>, &Task)> = tasks
+        .iter()
+        .flat_map(|task| upcoming_times(task).into_iter().map(move |at| (at, task)))
+        .collect();
+    upcoming.sort_by(|(a_at, a), (b_at, b)| a_at.cmp(b_at).then_with(|| task::display_order(a, b)));
+    upcoming
+}
+
+/// Returns the earliest upcoming reminder among `tasks`.
+pub fn next_reminder_in(tasks: &[Task]) -> Option<DateTime<Utc>> {
+    tasks.iter().filter_map(next_reminder).min()
+}
+
+/// Fires every reminder that has come due by `now`, marking it pending, and
+/// returns the positions of the tasks that fired, most urgent first. A task
+/// whose several reminders all came due while nothing was running fires
+/// only once.
+pub fn fire_due(tasks: &mut [Task], now: DateTime<Utc>) -> Vec<usize> {
+    fire_due_where(tasks, now, |_| true)
+}
+
+/// Like [`fire_due`], but only for the tasks `filter` accepts; the others
+/// are left untouched for whoever else reminds about them.
+pub fn fire_due_where<F: Fn(&Task) -> bool>(tasks: &mut [Task], now: DateTime<Utc>, filter: F) -> Vec<usize> {
+    let mut fired = Vec::new();
+    for (i, task) in tasks.iter_mut().enumerate() {
+        if filter(task) && next_reminder(task).is_some_and(|at| at <= now) {
+            task.reminder.fired_through = Some(now);
+            task.reminder.snoozed_until = None;
+            task.reminder.pending = true;
+            fired.push(i);
+        }
+    }
+    fired.sort_by(|&a, &b| task::display_order(&tasks[a], &tasks[b]));
+    fired
+}
+
+/// Returns the open tasks whose reminder has fired and awaits
+/// acknowledgement, most urgent first.
+pub fn pending(tasks: &[Task]) -> Vec<&Task> {
+    let mut pending: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| task.reminder.pending && !task.is_completed())
+        .collect();
+    pending.sort_by(|a, b| task::display_order(a, b));
+    pending
+}
+
+/// Returns the tasks that are due at `now`, most urgent first.
+pub fn due_tasks(tasks: &[Task], now: DateTime<Utc>) -> Vec<&Task> {
+    let mut due: Vec<&Task> = tasks.iter().filter(|task| task.is_due_at(now)).collect();
+    due.sort_by(|a, b| task::display_order(a, b));
+    due
+}
+
+/// Writes the reminder line for a single task, saying how long is left if
+/// it is not due yet at `now`. High and urgent tasks are flagged.
+pub fn write_reminder<W: Write>(mut writer: W, task: &Task, now: DateTime<Utc>) -> io::Result<()> {
+    write!(writer, "Reminder: ")?;
+    if task.priority >= Priority::High {
+        write!(writer, "[{}] ", task.priority.to_string().to_uppercase())?;
+    }
+    match task.due_date {
+        Some(due) if due > now => {
+            // Round up so a reminder firing a moment late still reads "30m".
+            let minutes = ((due - now).num_seconds() + 59) / 60;
+            writeln!(
+                writer,
+                "Task '{}' is due in {}.",
+                view::single_line(&task.description),
+                dates::format_lead_time(Duration::minutes(minutes))
+            )
+        }
+        _ => writeln!(writer, "Task '{}' is due!", view::single_line(&task.description)),
+    }
+}
+
+/// Writes a reminder line for every task that is due at `now`.
+pub fn write_reminders<W: Write>(mut writer: W, tasks: &[Task], now: DateTime<Utc>) -> io::Result<()> {
+    for task in due_tasks(tasks, now) {
+        write_reminder(&mut writer, task, now)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::TaskId;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn reminds_only_about_due_tasks() {
+        let now = Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 0).unwrap();
+        let tasks = vec![
+            Task::new(TaskId::generate(), "past".to_string(), Some(now - Duration::minutes(1))),
+            Task {
+                priority: Priority::Urgent,
+                ..Task::new(TaskId::generate(), "now".to_string(), Some(now))
+            },
+            Task::new(TaskId::generate(), "later".to_string(), Some(now + Duration::minutes(1))),
+            Task::new(TaskId::generate(), "undated".to_string(), None),
+        ];
+
+        let mut out = Vec::new();
+        write_reminders(&mut out, &tasks, now).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Reminder: [URGENT] Task 'now' is due!\nReminder: Task 'past' is due!\n"
+        );
+    }
+
+    #[test]
+    fn fires_once_until_snoozed() {
+        let due = Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 0).unwrap();
+        let mut tasks = vec![Task::new(TaskId::generate(), "call".to_string(), Some(due))];
+
+        assert!(fire_due(&mut tasks, due - Duration::seconds(1)).is_empty());
+        assert_eq!(fire_due(&mut tasks, due), vec![0]);
+        assert!(tasks[0].reminder.pending);
+        assert!(fire_due(&mut tasks, due + Duration::hours(1)).is_empty());
+
+        let later = due + Duration::hours(2);
+        tasks[0].reminder.snooze(later);
+        assert!(!tasks[0].reminder.pending);
+        assert_eq!(next_reminder(&tasks[0]), Some(later));
+        assert_eq!(fire_due(&mut tasks, later), vec![0]);
+
+        tasks[0].reminder.acknowledge();
+        assert_eq!(next_reminder(&tasks[0]), None);
+        assert!(pending(&tasks).is_empty());
+    }
+
+    #[test]
+    fn fires_each_lead_time_separately() {
+        let due = Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 0).unwrap();
+        let mut task = Task::new(TaskId::generate(), "call".to_string(), Some(due));
+        task.lead_times = vec![Duration::days(1), Duration::minutes(30), Duration::zero()];
+        let mut tasks = vec![task];
+
+        let day_before = due - Duration::days(1);
+        assert_eq!(next_reminder(&tasks[0]), Some(day_before));
+        assert_eq!(fire_due(&mut tasks, day_before), vec![0]);
+        let mut out = Vec::new();
+        write_reminder(&mut out, &tasks[0], day_before).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Reminder: Task 'call' is due in 1d.\n");
+        tasks[0].reminder.acknowledge();
+
+        let times: Vec<_> = upcoming(&tasks).into_iter().map(|(at, _)| at).collect();
+        assert_eq!(times, vec![due - Duration::minutes(30), due]);
+        assert_eq!(fire_due(&mut tasks, due - Duration::minutes(30)), vec![0]);
+        assert_eq!(next_reminder(&tasks[0]), Some(due));
+
+        // Reminders missed while nothing was running fire once, not twice.
+        assert_eq!(fire_due(&mut tasks, due + Duration::hours(1)), vec![0]);
+        assert_eq!(next_reminder(&tasks[0]), None);
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use chrono::Utc;
+
+use crate::reminder;
+use crate::task::Task;
+
+/// Longest the scheduler sleeps in one go. Waits are measured on the
+/// monotonic clock, so this bounds how late a reminder can be after the
+/// wall clock jumps or the machine resumes from suspend.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// A task list shared between the scheduler thread and its owner.
+pub type SharedTasks = Arc<Mutex<Vec<Task>>>;
+
+/// Locks `tasks`, carrying on with the data if another thread panicked
+/// while holding the lock.
+pub fn lock(tasks: &SharedTasks) -> MutexGuard<'_, Vec<Task>> {
+    tasks.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+enum Message {
+    Wake,
+    Shutdown,
+}
+
+/// Runs reminders on a background thread.
+///
+/// The thread sleeps until the next reminder among the shared tasks, fires
+/// it through [`reminder::fire_due`] (which records it in the task), and
+/// then calls `on_due` with a copy of each task that fired. Call
+/// [`Scheduler::wake`] after changing the tasks so it can recompute when to
+/// wake up next.
+pub struct Scheduler {
+    sender: Sender<Message>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Scheduler {
+    pub fn spawn<F>(tasks: SharedTasks, on_due: F) -> Scheduler
+    where
+        F: FnMut(&Task) + Send + 'static,
+    {
+        Scheduler::spawn_filtered(tasks, |_| true, on_due)
+    }
+
+    /// Like [`Scheduler::spawn`], but only reminds about the tasks `filter`
+    /// accepts.
+    pub fn spawn_filtered<P, F>(tasks: SharedTasks, filter: P, on_due: F) -> Scheduler
+    where
+        P: Fn(&Task) -> bool + Send + 'static,
+        F: FnMut(&Task) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let mut on_due = on_due;
+
+            loop {
+                let now = Utc::now();
+                let (fired, next) = {
+                    let mut tasks = lock(&tasks);
+                    let fired: Vec<Task> = reminder::fire_due_where(&mut tasks, now, &filter)
+                        .into_iter()
+                        .map(|i| tasks[i].clone())
+                        .collect();
+                    let next = tasks.iter().filter(|task| filter(task)).filter_map(reminder::next_reminder).min();
+                    (fired, next)
+                };
+                // Call back without holding the lock so `on_due` may use it.
+                for task in &fired {
+                    on_due(task);
+                }
+
+                let wait = next
+                    .map(|at| (at - now).to_std().unwrap_or_default().min(MAX_SLEEP))
+                    .unwrap_or(MAX_SLEEP);
+                match receiver.recv_timeout(wait) {
+                    Ok(Message::Wake) | Err(RecvTimeoutError::Timeout) => {}
+                    Ok(Message::Shutdown) | Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+
+        Scheduler { sender, thread: Some(thread) }
+    }
+
+    /// Tells the scheduler the tasks have changed.
+    pub fn wake(&self) {
+        // A send error means the thread has already stopped.
+        let _ = self.sender.send(Message::Wake);
+    }
+
+    /// Stops the scheduler thread and waits for it to finish.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        let _ = self.sender.send(Message::Shutdown);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::TaskId;
+
+    #[test]
+    fn fires_when_due_and_after_updates() {
+        let (sender, receiver) = mpsc::channel();
+        let soon = Task::new(TaskId::generate(), "soon".to_string(), Some(Utc::now() + chrono::Duration::milliseconds(50)));
+        let tasks: SharedTasks = Arc::new(Mutex::new(vec![soon]));
+        let scheduler = Scheduler::spawn(tasks.clone(), move |task| {
+            sender.send(task.description.clone()).unwrap();
+        });
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), "soon");
+        assert!(lock(&tasks)[0].reminder.pending);
+
+        let overdue = Task::new(TaskId::generate(), "overdue".to_string(), Some(Utc::now()));
+        lock(&tasks).push(overdue);
+        scheduler.wake();
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), "overdue");
+        // "soon" already fired and must not repeat.
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+
+        scheduler.shutdown();
+    }
+}
@@ -0,0 +1,727 @@
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufWriter};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use chrono::{DateTime, Duration, Utc};
+
+use todo_reminder::config::Config;
+use todo_reminder::dates::{self, DateSettings, DstChoice, ParsedDate, Resolution};
+use todo_reminder::notification::{self, Notifier};
+use todo_reminder::query::{self, Query};
+use todo_reminder::recurrence::{self, Recurrence};
+use todo_reminder::scheduler::lock;
+use todo_reminder::sinks::{self, RoutedNotifier};
+use todo_reminder::store;
+use todo_reminder::tags::{self, Tag};
+use todo_reminder::task::{self, Priority};
+use todo_reminder::view;
+use todo_reminder::{reminder, Scheduler, SharedTasks, Task, TaskId, TaskStore};
+
+/// Where menu exports go unless the user names another file.
+const DEFAULT_EXPORT: &str = "exported_tasks.csv";
+
+/// How to take back the last edit or delete. Only the task concerned is
+/// kept, so undoing leaves the rest of the list as it is now.
+enum Undo {
+    /// The task as it was before it was edited.
+    Edit(Task),
+    /// A deleted task and where it was in the list.
+    Delete(Task, usize),
+}
+
+/// What the user typed at a due date prompt.
+enum DueAnswer {
+    Blank,
+    Clear,
+    Date(DateTime<Utc>),
+}
+
+/// What the menu waits for.
+enum Event {
+    /// A line of input, or `None` at the end of the input.
+    Line(io::Result<Option<String>>),
+    /// A reminder fired or a notification action changed the tasks.
+    Changed,
+}
+
+/// The tasks the menu works on, where they are stored and the input it
+/// reads. While it waits for input, changes made in the background are
+/// saved as they happen.
+struct Session<'a> {
+    store: &'a mut dyn TaskStore,
+    tasks: SharedTasks,
+    /// The tasks as last saved or loaded.
+    saved: Vec<Task>,
+    scheduler: Scheduler,
+    events: Receiver<Event>,
+    ended: bool,
+    /// How dates are read and shown.
+    dates: &'a DateSettings,
+    /// How to take back the last edit or delete, if there is one.
+    undo: Option<Undo>,
+}
+
+impl Session<'_> {
+    /// Waits for the next line of input, saving what reminders and
+    /// notification actions change meanwhile. Returns `None` at the end of
+    /// the input.
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        while !self.ended {
+            match self.events.recv() {
+                Ok(Event::Line(Ok(Some(line)))) => return Ok(Some(line)),
+                Ok(Event::Line(line)) => {
+                    self.ended = true;
+                    return line;
+                }
+                Ok(Event::Changed) => self.sync_or_report(),
+                Err(_) => self.ended = true,
+            }
+        }
+        Ok(None)
+    }
+
+    /// Prints `message` and returns the trimmed line the user typed, or an
+    /// error if the input ends first.
+    fn prompt(&mut self, message: &str) -> io::Result<String> {
+        print!("{}", message);
+        io::Write::flush(&mut io::stdout())?;
+        match self.read_line()? {
+            Some(input) => Ok(input.trim().to_string()),
+            None => {
+                println!();
+                Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the input ended before an answer was given"))
+            }
+        }
+    }
+
+    /// Saves the tasks if they changed and picks up what other instances
+    /// saved, letting the scheduler know if that changed them.
+    fn sync(&mut self) -> io::Result<()> {
+        if store::sync(self.store, &self.tasks, &mut self.saved)? {
+            self.scheduler.wake();
+        }
+        Ok(())
+    }
+
+    /// Syncs with the store, printing rather than returning an error so the
+    /// session can go on.
+    fn sync_or_report(&mut self) {
+        if let Err(e) = self.sync() {
+            println!("Could not save the tasks: {}", e);
+        }
+    }
+}
+
+/// Reads the input on a thread of its own, so the menu can react to
+/// changes made in the background while the user has not typed anything.
+fn read_input(events: Sender<Event>) {
+    thread::spawn(move || loop {
+        let mut line = String::new();
+        let event = match io::stdin().lock().read_line(&mut line) {
+            Ok(0) => Event::Line(Ok(None)),
+            Ok(_) => Event::Line(Ok(Some(line))),
+            Err(e) => Event::Line(Err(e)),
+        };
+        let more = matches!(event, Event::Line(Ok(Some(_))));
+        if events.send(event).is_err() || !more {
+            break;
+        }
+    });
+}
+
+/// Runs the numbered interactive menu until the user chooses to exit.
+/// New tasks remind at the configured lead times unless the user picks
+/// others, and task lists are shown in the configured order and colors.
+pub fn run<S: TaskStore>(store: &mut S, config: &Config) -> io::Result<()> {
+    // Load existing tasks
+    let saved = store.load()?;
+    let tasks: SharedTasks = Arc::new(Mutex::new(saved.clone()));
+    let (sender, events) = mpsc::channel();
+
+    // Reminders are shown from a background thread as tasks fall due,
+    // even while the menu is waiting for input, and notification actions
+    // are carried out as they are clicked. Both tell the menu, which saves
+    // their changes straight away. The task list is only locked while it
+    // is read or changed, never while prompting.
+    let mut rules = config.sinks.clone();
+    if let Some(path) = store.path() {
+        rules.extend(sinks::load_rules(&sinks::sinks_path(path))?);
+    }
+    let acted = tasks.clone();
+    let changed = sender.clone();
+    let desktop = notification::desktop_or_console(config.delivery, move |id, action| {
+        if let Some(message) = notification::apply_action(&mut lock(&acted), id, action, Utc::now()) {
+            println!();
+            println!("{}", message);
+            let _ = changed.send(Event::Changed);
+        }
+    });
+    let mut notifier = RoutedNotifier::new(desktop, &rules);
+    let hours = config.reminder_hours();
+    let filter = move |_: &Task| hours.as_ref().is_none_or(|hours| hours.contains(Utc::now()));
+    let fired = sender.clone();
+    let scheduler = Scheduler::spawn_filtered(tasks.clone(), filter, move |task| {
+        println!();
+        if let Err(e) = notifier.notify(task, Utc::now()) {
+            println!("Could not show a reminder: {}", e);
+        }
+        let _ = fired.send(Event::Changed);
+    });
+    read_input(sender);
+    let mut session =
+        Session { store, tasks, saved, scheduler, events, ended: false, dates: &config.dates, undo: None };
+
+    // The scheduler is stopped and the tasks saved even when the menu
+    // fails, e.g. because the input ended mid-prompt.
+    let result = menu(&mut session, config);
+    let Session { store, tasks, mut saved, scheduler, .. } = session;
+    scheduler.shutdown();
+    store::sync(store, &tasks, &mut saved)?;
+    result
+}
+
+/// Shows the menu and carries out the user's choices until they exit or
+/// the input ends.
+fn menu(session: &mut Session<'_>, config: &Config) -> io::Result<()> {
+    let order = &config.order;
+    let style = config.table_style();
+    let tasks = session.tasks.clone();
+    loop {
+        let pending = reminder::pending(&lock(&tasks)).len();
+        println!("Todo List Manager");
+        println!("1. Add a new task");
+        println!("2. View all tasks");
+        println!("3. Export tasks to CSV");
+        println!("4. Mark a task as done");
+        println!("5. Reopen a task");
+        println!("6. Edit a task");
+        println!("7. Delete a task");
+        println!("8. Undo last edit or delete");
+        println!("9. Acknowledge or snooze reminders ({} pending)", pending);
+        println!("10. View upcoming reminders");
+        println!("11. List tags");
+        println!("12. Filter tasks");
+        println!("0. Exit");
+        print!("Enter your choice: ");
+        io::Write::flush(&mut io::stdout())?;
+
+        let choice = match session.read_line()? {
+            Some(choice) => choice,
+            None => {
+                // End of input, as with Ctrl-D.
+                println!();
+                return Ok(());
+            }
+        };
+        // Pick up what other instances saved while waiting for input.
+        session.sync_or_report();
+        match choice.trim() {
+            "1" => add_task(session, &config.lead_times)?,
+            "2" => {
+                let mut sorted = lock(&tasks).clone();
+                view::write_ordered_table(io::stdout(), &mut sorted, order, &style)?;
+            }
+            "3" => export_tasks_to_csv(session)?,
+            "4" => {
+                if let Some(selected) = select_task(session, "Enter the ID of the task to mark as done: ")? {
+                    let mut tasks = lock(&tasks);
+                    if let Some(index) = task::position(&tasks, selected.id) {
+                        let next = task::complete(&mut tasks, index, Utc::now());
+                        println!("Completed: {}", selected.description);
+                        if let Some(due) = next.and_then(|i| tasks[i].due_date) {
+                            println!("Next occurrence due {}", dates::format_local(due, &config.dates));
+                        }
+                    }
+                }
+            }
+            "5" => {
+                if let Some(selected) = select_task(session, "Enter the ID of the task to reopen: ")? {
+                    update_task(&tasks, selected.id, Task::reopen);
+                    println!("Reopened: {}", selected.description);
+                }
+            }
+            "6" => {
+                if let Some(mut edited) = select_task(session, "Enter the ID of the task to edit: ")? {
+                    let before = edited.clone();
+                    if edit_task(session, &mut edited)? {
+                        update_task(&tasks, edited.id, |task| copy_edits(task, &edited));
+                        session.undo = Some(Undo::Edit(before));
+                    }
+                }
+            }
+            "7" => {
+                if let Some((deleted, index)) = delete_task(session)? {
+                    session.undo = Some(Undo::Delete(deleted, index));
+                }
+            }
+            "8" => undo_last(session),
+            "9" => handle_reminders(session)?,
+            "10" => {
+                let tasks = lock(&tasks);
+                let upcoming = reminder::upcoming(&tasks);
+                if upcoming.is_empty() {
+                    println!("No upcoming reminders.");
+                }
+                view::write_upcoming(io::stdout(), &upcoming, &config.dates)?;
+            }
+            "11" => {
+                let counts = tags::open_counts(&lock(&tasks));
+                if counts.is_empty() {
+                    println!("No tags.");
+                }
+                view::write_tag_counts(io::stdout(), &counts)?;
+            }
+            "12" => {
+                if let Some(query) = prompt_query(session)? {
+                    let mut matching: Vec<Task> = lock(&tasks).iter().filter(|task| query.matches(task)).cloned().collect();
+                    if matching.is_empty() {
+                        println!("No matching tasks.");
+                    }
+                    view::write_ordered_table(io::stdout(), &mut matching, order, &style)?;
+                }
+            }
+            "0" => return Ok(()),
+            _ => println!("Invalid choice. Please try again."),
+        }
+
+        // Save the change just made. Those made in the background were
+        // saved as they happened, in `Session::read_line`.
+        session.sync_or_report();
+        session.scheduler.wake();
+    }
+}
+
+fn add_task(session: &mut Session<'_>, default_lead_times: &[Duration]) -> io::Result<()> {
+    let description = session.prompt("Enter task description: ")?;
+
+    let due_date = match prompt_due_date(
+        session,
+        "Enter due date (optional, e.g. 'tomorrow 9am', 'next friday', 'in 2 hours' or YYYY-MM-DD HH:MM) or leave blank for no due date: ",
+    )? {
+        DueAnswer::Date(date) => Some(date),
+        DueAnswer::Blank | DueAnswer::Clear => None,
+    };
+    let mut task = Task::new(TaskId::generate(), description, due_date);
+    if let Some(priority) = prompt_priority(session, "Priority (low, normal, high, urgent) [normal]: ")? {
+        task.priority = priority;
+    }
+    if let Some(tags) = prompt_tags(session, "Extra tags (e.g. +work @home) or leave blank: ")? {
+        task.add_tags(tags);
+    }
+    task.lead_times = default_lead_times.to_vec();
+    if due_date.is_some() {
+        let current = view::describe_lead_times(&task);
+        if let Some(lead_times) = prompt_lead_times(session, &format!("Remind how long before? [{}] ", current))? {
+            task.lead_times = lead_times;
+        }
+        task.recurrence = prompt_recurrence(
+            session,
+            "Repeat (e.g. 'daily', 'every monday', 'monthly on the last friday') or leave blank for no repeat: ",
+        )?
+        .flatten();
+    }
+    lock(&session.tasks).push(task);
+
+    Ok(())
+}
+
+/// Takes back the last edit or delete, leaving every other task as it is
+/// now.
+fn undo_last(session: &mut Session<'_>) {
+    match session.undo.take() {
+        Some(Undo::Edit(before)) => {
+            let mut tasks = lock(&session.tasks);
+            match task::position(&tasks, before.id) {
+                Some(i) => {
+                    let task = &mut tasks[i];
+                    // The edit re-armed the reminder if it moved the due date.
+                    let rearmed = task.due_date != before.due_date;
+                    copy_edits(task, &before);
+                    if rearmed {
+                        task.reminder = before.reminder.clone();
+                    }
+                    println!("Undid the edit of task {}.", before.id.short());
+                }
+                None => println!("Task {} no longer exists.", before.id.short()),
+            }
+        }
+        Some(Undo::Delete(deleted, index)) => {
+            let mut tasks = lock(&session.tasks);
+            if task::position(&tasks, deleted.id).is_none() {
+                let index = index.min(tasks.len());
+                println!("Undid the deletion of task {}.", deleted.id.short());
+                tasks.insert(index, deleted);
+            } else {
+                println!("Task {} is back already.", deleted.id.short());
+            }
+        }
+        None => println!("Nothing to undo."),
+    }
+}
+
+/// Prompts for each editable field, keeping the current value on a blank
+/// answer. Returns whether anything changed.
+fn edit_task(session: &mut Session<'_>, task: &mut Task) -> io::Result<bool> {
+    let original = task.clone();
+
+    let description = session.prompt(&format!("Description [{}]: ", task.description))?;
+    if !description.is_empty() {
+        task.set_description(description);
+    }
+    let current: Vec<String> = task.tags.iter().map(Tag::to_string).collect();
+    let message = format!("Tags [{}] (a new list replaces them, 'none' removes all): ", current.join(" "));
+    if let Some(tags) = prompt_tags(session, &message)? {
+        task.tags = tags;
+    }
+
+    if let Some(priority) = prompt_priority(session, &format!("Priority [{}]: ", task.priority))? {
+        task.priority = priority;
+    }
+
+    let current = match task.due_date {
+        Some(date) => dates::format_local(date, session.dates),
+        None => "none".to_string(),
+    };
+    match prompt_due_date(session, &format!("Due date [{}] ('none' to clear): ", current))? {
+        DueAnswer::Blank => {}
+        DueAnswer::Clear => task.set_due_date(None),
+        DueAnswer::Date(date) => task.set_due_date(Some(date)),
+    }
+
+    if task.due_date.is_some() {
+        let current = view::describe_lead_times(task);
+        if let Some(lead_times) = prompt_lead_times(session, &format!("Remind how long before [{}]: ", current))? {
+            task.lead_times = lead_times;
+        }
+        let current = task.recurrence.as_ref().map_or("no repeat".to_string(), |r| r.describe());
+        if let Some(recurrence) = prompt_recurrence(session, &format!("Repeat [{}] ('none' to stop): ", current))? {
+            task.recurrence = recurrence;
+        }
+    } else {
+        task.recurrence = None;
+    }
+
+    let changed = *task != original;
+    println!("{}", if changed { "Task updated." } else { "No changes made." });
+    Ok(changed)
+}
+
+/// Deletes a task after confirmation, returning it and where it was in the
+/// list if it was removed.
+fn delete_task(session: &mut Session<'_>) -> io::Result<Option<(Task, usize)>> {
+    let selected = match select_task(session, "Enter the ID of the task to delete: ")? {
+        Some(task) => task,
+        None => return Ok(None),
+    };
+
+    let answer = session.prompt(&format!("Delete task {} '{}'? [y/N] ", selected.id.short(), selected.description))?;
+    if !answer.eq_ignore_ascii_case("y") {
+        println!("Task kept.");
+        return Ok(None);
+    }
+    let mut tasks = lock(&session.tasks);
+    let index = match task::position(&tasks, selected.id) {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+    let deleted = tasks.remove(index);
+    println!("Task deleted. Choose 'Undo' to bring it back.");
+    Ok(Some((deleted, index)))
+}
+
+/// Lists pending reminders and lets the user acknowledge or snooze one.
+fn handle_reminders(session: &mut Session<'_>) -> io::Result<()> {
+    let tasks = session.tasks.clone();
+    let pending: Vec<Task> = reminder::pending(&lock(&tasks)).into_iter().cloned().collect();
+    if pending.is_empty() {
+        println!("No pending reminders.");
+        return Ok(());
+    }
+    view::write_task_list(io::stdout(), &pending, session.dates)?;
+
+    let selected = match select_task(session, "Enter the ID of the reminder to handle: ")? {
+        Some(task) => task,
+        None => return Ok(()),
+    };
+    loop {
+        let answer = session.prompt("Type 'a' to acknowledge, or how long to snooze (e.g. 10m, 1h, tomorrow morning): ")?;
+        if answer.eq_ignore_ascii_case("a") {
+            update_task(&tasks, selected.id, |task| task.reminder.acknowledge());
+            println!("Reminder acknowledged.");
+            return Ok(());
+        }
+        match dates::parse_snooze(&answer, session.dates) {
+            Ok(until) => {
+                update_task(&tasks, selected.id, |task| task.reminder.snooze(until));
+                println!("Snoozed until {}.", dates::format_local(until, session.dates));
+                return Ok(());
+            }
+            Err(e) => println!("{}. Please try again.", e),
+        }
+    }
+}
+
+/// Copies the fields the menu edits from `from` to `task`.
+fn copy_edits(task: &mut Task, from: &Task) {
+    task.description = from.description.clone();
+    task.tags = from.tags.clone();
+    task.priority = from.priority;
+    task.set_due_date(from.due_date);
+    task.lead_times = from.lead_times.clone();
+    task.recurrence = from.recurrence.clone();
+}
+
+/// Applies `update` to the task with `id`, if it still exists.
+fn update_task<F: FnOnce(&mut Task)>(tasks: &SharedTasks, id: TaskId, update: F) {
+    let mut tasks = lock(tasks);
+    if let Some(i) = task::position(&tasks, id) {
+        update(&mut tasks[i]);
+    }
+}
+
+/// Prompts for a task id (or a unique prefix of one) as shown by "View all
+/// tasks" and returns a copy of the task.
+fn select_task(session: &mut Session<'_>, message: &str) -> io::Result<Option<Task>> {
+    let input = session.prompt(message)?;
+    let tasks = lock(&session.tasks);
+    match task::find(&tasks, &input) {
+        Ok(i) => Ok(Some(tasks[i].clone())),
+        Err(e) => {
+            println!("{}.", e);
+            Ok(None)
+        }
+    }
+}
+
+/// Reads due dates until one parses and is confirmed, or the answer is
+/// blank or "none".
+fn prompt_due_date(session: &mut Session<'_>, message: &str) -> io::Result<DueAnswer> {
+    loop {
+        let input = session.prompt(message)?;
+        if input.is_empty() {
+            return Ok(DueAnswer::Blank);
+        }
+        if input.eq_ignore_ascii_case("none") {
+            return Ok(DueAnswer::Clear);
+        }
+
+        match dates::parse_due_date(&input, session.dates) {
+            Ok(parsed) => {
+                let date = match parsed.resolution.unique() {
+                    Some(date) => date,
+                    None => choose_dst(session, &parsed)?,
+                };
+                let answer = session.prompt(&format!("Due {}. Is that right? [Y/n] ", parsed.zone.format(date)))?;
+                if !answer.eq_ignore_ascii_case("n") {
+                    return Ok(DueAnswer::Date(date));
+                }
+            }
+            Err(e) => {
+                println!("Invalid date format. Please try again. Error: {}", e);
+            }
+        }
+    }
+}
+
+/// Reads reminder lead times such as "1d, 30m, 0" until they parse,
+/// returning `None` on a blank answer.
+fn prompt_lead_times(session: &mut Session<'_>, message: &str) -> io::Result<Option<Vec<Duration>>> {
+    loop {
+        let input = session.prompt(message)?;
+        if input.is_empty() {
+            return Ok(None);
+        }
+        match dates::parse_lead_times(&input) {
+            Ok(lead_times) => return Ok(Some(lead_times)),
+            Err(e) => println!("{}. Please try again.", e),
+        }
+    }
+}
+
+/// Reads a filter such as `+work and due<tomorrow`, or the name of a view,
+/// until it parses. Returns `None` on a blank answer.
+fn prompt_query(session: &mut Session<'_>) -> io::Result<Option<Query>> {
+    let saved = match session.store.path() {
+        Some(path) => query::load_views(&query::views_path(path))?,
+        None => Vec::new(),
+    };
+    let views = query::all_views(&saved);
+    let names: Vec<&str> = views.iter().map(|view| view.name.as_str()).collect();
+    loop {
+        let input = session.prompt(&format!("Enter a filter or a view ({}): ", names.join(", ")))?;
+        if input.is_empty() {
+            return Ok(None);
+        }
+        let text = views.iter().find(|view| view.name == input).map_or(input.as_str(), |view| view.query.as_str());
+        match query::parse_query(text, session.dates) {
+            Ok(query) => return Ok(Some(query)),
+            Err(e) => println!("{}. Please try again.", e),
+        }
+    }
+}
+
+/// Reads a priority until one parses, returning `None` on a blank answer.
+fn prompt_priority(session: &mut Session<'_>, message: &str) -> io::Result<Option<Priority>> {
+    loop {
+        let input = session.prompt(message)?;
+        if input.is_empty() {
+            return Ok(None);
+        }
+        match input.parse() {
+            Ok(priority) => return Ok(Some(priority)),
+            Err(e) => println!("{}. Please try again.", e),
+        }
+    }
+}
+
+/// Reads a list of tags until it parses, returning `None` on a blank
+/// answer and an empty list for "none".
+fn prompt_tags(session: &mut Session<'_>, message: &str) -> io::Result<Option<Vec<Tag>>> {
+    loop {
+        let input = session.prompt(message)?;
+        if input.is_empty() {
+            return Ok(None);
+        }
+        if input.eq_ignore_ascii_case("none") {
+            return Ok(Some(Vec::new()));
+        }
+        match tags::parse_tag_list(&input) {
+            Ok(tags) => return Ok(Some(tags)),
+            Err(e) => println!("{}. Please try again.", e),
+        }
+    }
+}
+
+/// Reads a recurrence rule until one parses. Returns `None` on a blank
+/// answer and `Some(None)` for "none".
+fn prompt_recurrence(session: &mut Session<'_>, message: &str) -> io::Result<Option<Option<Recurrence>>> {
+    loop {
+        let input = session.prompt(message)?;
+        if input.is_empty() {
+            return Ok(None);
+        }
+        if input.eq_ignore_ascii_case("none") {
+            return Ok(Some(None));
+        }
+        match recurrence::parse_recurrence(&input, session.dates) {
+            Ok(rule) => return Ok(Some(Some(rule))),
+            Err(e) => println!("{}. Please try again.", e),
+        }
+    }
+}
+
+/// Asks which instant was meant when a wall-clock time falls into a
+/// daylight saving gap or overlap.
+fn choose_dst(session: &mut Session<'_>, parsed: &ParsedDate) -> io::Result<DateTime<Utc>> {
+    let earlier = parsed.resolution.choose(DstChoice::Earlier);
+    let later = parsed.resolution.choose(DstChoice::Later);
+    match parsed.resolution {
+        Resolution::Ambiguous { .. } => {
+            println!("That time happens twice in {} because clocks are turned back.", parsed.zone)
+        }
+        _ => println!("That time does not exist in {} because clocks jump forward.", parsed.zone),
+    }
+    println!("1. {}", parsed.zone.format(earlier));
+    println!("2. {}", parsed.zone.format(later));
+
+    loop {
+        match session.prompt("Which did you mean? ")?.as_str() {
+            "1" => return Ok(earlier),
+            "2" => return Ok(later),
+            _ => println!("Please enter 1 or 2."),
+        }
+    }
+}
+
+/// Exports to a file the user names, in the current directory unless the
+/// path says otherwise. Only the end of the input is an error; a file that
+/// cannot be written is reported and the menu goes on.
+fn export_tasks_to_csv(session: &mut Session<'_>) -> io::Result<()> {
+    let answer = session.prompt(&format!("Export to file [{}]: ", DEFAULT_EXPORT))?;
+    let path = if answer.is_empty() { DEFAULT_EXPORT } else { answer.as_str() };
+    let exported = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .and_then(|file| view::write_csv_export(BufWriter::new(file), &lock(&session.tasks)));
+    match exported {
+        Ok(()) => println!("Tasks have been exported to {}", path),
+        Err(e) => println!("Could not export to {}: {}", path, e),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use todo_reminder::MemoryStore;
+
+    fn tasks(descriptions: &[&str]) -> Vec<Task> {
+        descriptions.iter().map(|d| Task::new(TaskId::generate(), d.to_string(), None)).collect()
+    }
+
+    /// Runs the menu on `tasks` with `lines` as its input and returns the
+    /// tasks it saved.
+    fn run_menu(tasks: Vec<Task>, lines: &[String]) -> Vec<Task> {
+        let config = Config::default();
+        let mut store = MemoryStore { tasks: tasks.clone() };
+        let (sender, events) = mpsc::channel();
+        for line in lines {
+            sender.send(Event::Line(Ok(Some(format!("{}\n", line))))).unwrap();
+        }
+        // The input ends after the last line.
+        drop(sender);
+        let shared: SharedTasks = Arc::new(Mutex::new(tasks.clone()));
+        let scheduler = Scheduler::spawn(shared.clone(), |_| {});
+        let mut session = Session {
+            store: &mut store,
+            tasks: shared,
+            saved: tasks,
+            scheduler,
+            events,
+            ended: false,
+            dates: &config.dates,
+            undo: None,
+        };
+        menu(&mut session, &config).unwrap();
+        store.tasks
+    }
+
+    fn descriptions(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|task| task.description.as_str()).collect()
+    }
+
+    #[test]
+    fn undo_restores_only_the_edited_task() {
+        let before = tasks(&["a", "b"]);
+        let (a, b) = (before[0].id.short(), before[1].id.short());
+        // Edit a's description, keeping the other fields, then complete b.
+        let input = ["6", &a, "changed", "", "", "", "4", &b, "8"].map(str::to_string);
+        let edited = run_menu(before.clone(), &input[..8]);
+        assert_eq!(descriptions(&edited), ["changed", "b"]);
+        let after = run_menu(before, &input);
+        assert_eq!(descriptions(&after), ["a", "b"]);
+        assert!(after[1].is_completed());
+    }
+
+    #[test]
+    fn a_failed_export_returns_to_the_menu() {
+        let input = ["3", "/nonexistent/exported.csv", "1", "b", "", "", ""].map(str::to_string);
+        let after = run_menu(tasks(&["a"]), &input);
+        assert_eq!(descriptions(&after), ["a", "b"]);
+    }
+
+    #[test]
+    fn undo_brings_back_only_the_deleted_task() {
+        let before = tasks(&["a", "b", "c"]);
+        let b = before[1].id.short();
+        // Delete b, add d, then undo the delete.
+        let input = ["7", &b, "y", "1", "d", "", "", "", "8"].map(str::to_string);
+        let deleted = run_menu(before.clone(), &input[..8]);
+        assert_eq!(descriptions(&deleted), ["a", "c", "d"]);
+        let after = run_menu(before, &input);
+        assert_eq!(descriptions(&after), ["a", "b", "c", "d"]);
+    }
+}
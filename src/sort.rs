@@ -0,0 +1,331 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use chrono::{DateTime, Duration, Utc, Weekday};
+
+use crate::dates::{self, Zone};
+use crate::tags::{Tag, TagKind};
+use crate::task::{self, Task};
+
+/// A property tasks can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Due,
+    Created,
+    Priority,
+    Description,
+    Status,
+}
+
+impl SortField {
+    /// Whether the field sorts descending when no direction is given:
+    /// only priority does, so the most urgent tasks come first.
+    fn descending_by_default(self) -> bool {
+        self == SortField::Priority
+    }
+}
+
+/// One field of a sort order, with its direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.field {
+            SortField::Due => "due",
+            SortField::Created => "created",
+            SortField::Priority => "priority",
+            SortField::Description => "description",
+            SortField::Status => "status",
+        };
+        write!(f, "{}:{}", name, if self.descending { "desc" } else { "asc" })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseSortError {
+    message: String,
+}
+
+impl fmt::Display for ParseSortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ParseSortError {}
+
+/// Parses a key such as `due`, `priority:asc` or `created:desc`.
+impl FromStr for SortKey {
+    type Err = ParseSortError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, direction) = match s.trim().split_once(':') {
+            Some((name, direction)) => (name.trim(), Some(direction.trim())),
+            None => (s.trim(), None),
+        };
+        let field = match name.to_lowercase().as_str() {
+            "due" => SortField::Due,
+            "created" => SortField::Created,
+            "priority" | "pri" => SortField::Priority,
+            "description" | "desc" => SortField::Description,
+            "status" => SortField::Status,
+            _ => {
+                return Err(ParseSortError {
+                    message: format!(
+                        "unknown sort key '{}'; use due, created, priority, description or status",
+                        name
+                    ),
+                })
+            }
+        };
+        let descending = match direction.map(str::to_lowercase).as_deref() {
+            None => field.descending_by_default(),
+            Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => {
+                return Err(ParseSortError { message: format!("sort direction must be 'asc' or 'desc', not '{}'", other) })
+            }
+        };
+        Ok(SortKey { field, descending })
+    }
+}
+
+/// Parses a comma-separated list of sort keys, most significant first,
+/// e.g. `status, priority:desc, due`.
+pub fn parse_sort_keys(input: &str) -> Result<Vec<SortKey>, ParseSortError> {
+    let keys = input.split(',').filter(|key| !key.trim().is_empty()).map(str::parse).collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        return Err(ParseSortError { message: "expected at least one sort key".to_string() });
+    }
+    Ok(keys)
+}
+
+/// Compares two tasks by `keys` in turn. Ties, and an empty `keys`, fall
+/// back to [`task::display_order`].
+///
+/// Tasks without a due date sort after dated ones in either direction, as
+/// do tasks whose creation time is unknown. Ascending status puts open
+/// tasks first.
+pub fn compare(a: &Task, b: &Task, keys: &[SortKey]) -> Ordering {
+    keys.iter()
+        .map(|key| {
+            let ordering = match key.field {
+                SortField::Due => return compare_optional(a.due_date, b.due_date, key.descending),
+                SortField::Created => return compare_optional(a.created_at, b.created_at, key.descending),
+                SortField::Priority => a.priority.cmp(&b.priority),
+                SortField::Description => {
+                    a.description.to_lowercase().cmp(&b.description.to_lowercase())
+                }
+                SortField::Status => a.is_completed().cmp(&b.is_completed()),
+            };
+            if key.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| task::display_order(a, b))
+}
+
+/// Sorts `tasks` by `keys`; see [`compare`]. The sort is stable.
+pub fn sort_tasks(tasks: &mut [Task], keys: &[SortKey]) {
+    tasks.sort_by(|a, b| compare(a, b, keys));
+}
+
+fn compare_optional(a: Option<DateTime<Utc>>, b: Option<DateTime<Utc>>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// How a task list is split into groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// Overdue, today, tomorrow, this week, later and no due date.
+    Due,
+    /// Every tag; a task with several tags is listed under each.
+    Tag,
+    /// Only `+project` tags.
+    Project,
+}
+
+impl FromStr for Grouping {
+    type Err = ParseSortError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "due" => Ok(Grouping::Due),
+            "tag" | "tags" => Ok(Grouping::Tag),
+            "project" | "projects" => Ok(Grouping::Project),
+            other => Err(ParseSortError { message: format!("unknown grouping '{}'; use due, tag or project", other) }),
+        }
+    }
+}
+
+impl fmt::Display for Grouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Grouping::Due => write!(f, "due"),
+            Grouping::Tag => write!(f, "tag"),
+            Grouping::Project => write!(f, "project"),
+        }
+    }
+}
+
+/// How task lists are sorted and, optionally, grouped. The default sorts
+/// by [`task::display_order`] without groups.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListOrder {
+    pub keys: Vec<SortKey>,
+    pub grouping: Option<Grouping>,
+}
+
+/// A titled run of tasks, as returned by [`group_tasks`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group<'a> {
+    pub title: String,
+    pub tasks: Vec<&'a Task>,
+}
+
+/// Splits `tasks` into groups, keeping their order within each group.
+/// Empty groups are left out.
+///
+/// Due date buckets are read in `zone` relative to `now`, with weeks
+/// starting on `week_start` and so ending the day before it. Open tasks
+/// past their due time are overdue; done tasks from before today are
+/// listed as earlier.
+pub fn group_tasks<'a>(
+    tasks: &'a [Task],
+    grouping: Grouping,
+    now: DateTime<Utc>,
+    zone: Zone,
+    week_start: Weekday,
+) -> Vec<Group<'a>> {
+    let mut groups: Vec<Group<'a>> = match grouping {
+        Grouping::Due => {
+            let today = zone.wall_clock(now).date();
+            let end_of_week = dates::end_of_week(today, week_start);
+            let titles = ["Overdue", "Earlier", "Today", "Tomorrow", "This week", "Later", "No due date"];
+            let mut buckets: Vec<Vec<&Task>> = vec![Vec::new(); titles.len()];
+            for task in tasks {
+                let bucket = match task.due_date {
+                    None => 6,
+                    Some(due) if due < now && !task.is_completed() => 0,
+                    Some(due) => match zone.wall_clock(due).date() {
+                        day if day < today => 1,
+                        day if day == today => 2,
+                        day if day == today + Duration::days(1) => 3,
+                        day if day <= end_of_week => 4,
+                        _ => 5,
+                    },
+                };
+                buckets[bucket].push(task);
+            }
+            titles.iter().zip(buckets).map(|(title, tasks)| Group { title: title.to_string(), tasks }).collect()
+        }
+        Grouping::Tag | Grouping::Project => {
+            let wanted = |tag: &&Tag| grouping == Grouping::Tag || tag.kind == TagKind::Project;
+            let mut names: Vec<&Tag> = tasks.iter().flat_map(|task| task.tags.iter().filter(wanted)).collect();
+            names.sort();
+            names.dedup();
+            let mut groups: Vec<Group<'a>> = names
+                .into_iter()
+                .map(|tag| Group {
+                    title: tag.to_string(),
+                    tasks: tasks.iter().filter(|task| task.tags.contains(tag)).collect(),
+                })
+                .collect();
+            let untagged = tasks.iter().filter(|task| !task.tags.iter().any(|tag| wanted(&tag))).collect();
+            let title = if grouping == Grouping::Tag { "No tags" } else { "No project" };
+            groups.push(Group { title: title.to_string(), tasks: untagged });
+            groups
+        }
+    };
+    groups.retain(|group| !group.tasks.is_empty());
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Priority, TaskId};
+
+    fn task(description: &str, due: Option<&str>) -> Task {
+        Task::new(TaskId::generate(), description.to_string(), due.map(|due| due.parse().unwrap()))
+    }
+
+    fn descriptions<'a, I: IntoIterator<Item = &'a Task>>(tasks: I) -> Vec<&'a str> {
+        tasks.into_iter().map(|task| task.description.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_by_keys_in_turn() {
+        let mut done = task("b done", Some("2026-11-01T09:00:00Z"));
+        done.complete("2026-11-01T10:00:00Z".parse().unwrap());
+        let mut tasks = vec![
+            task("c", None),
+            done,
+            Task { priority: Priority::High, ..task("a", Some("2026-11-03T09:00:00Z")) },
+            task("d", Some("2026-11-02T09:00:00Z")),
+        ];
+        sort_tasks(&mut tasks, &parse_sort_keys("status, due:desc").unwrap());
+        assert_eq!(descriptions(&tasks), ["a", "d", "c", "b done"]);
+        sort_tasks(&mut tasks, &parse_sort_keys("description:desc").unwrap());
+        assert_eq!(descriptions(&tasks), ["d", "c", "b done", "a"]);
+        sort_tasks(&mut tasks, &parse_sort_keys("priority:asc").unwrap());
+        assert_eq!(descriptions(&tasks), ["b done", "d", "c", "a"]);
+        assert!(parse_sort_keys("urgency").is_err());
+        assert!(parse_sort_keys("due:up").is_err());
+    }
+
+    #[test]
+    fn groups_by_due_bucket_and_tag() {
+        // Wednesday 14 October 2026, 15:30 in Berlin.
+        let now = "2026-10-14T13:30:00Z".parse().unwrap();
+        let zone = Zone::parse("Europe/Berlin").unwrap();
+        let tasks = [
+            task("overdue +home", Some("2026-10-14T08:00:00Z")),
+            task("tonight +work", Some("2026-10-14T20:00:00Z")),
+            task("tomorrow +work #call", Some("2026-10-15T08:00:00Z")),
+            task("saturday", Some("2026-10-17T08:00:00Z")),
+            task("next week", Some("2026-10-19T08:00:00Z")),
+            task("someday", None),
+        ];
+        fn summary(groups: Vec<Group<'_>>) -> Vec<(String, Vec<&str>)> {
+            groups.into_iter().map(|group| (group.title, descriptions(group.tasks))).collect()
+        }
+        assert_eq!(
+            summary(group_tasks(&tasks, Grouping::Due, now, zone, Weekday::Mon)),
+            [
+                ("Overdue".to_string(), vec!["overdue +home"]),
+                ("Today".to_string(), vec!["tonight +work"]),
+                ("Tomorrow".to_string(), vec!["tomorrow +work #call"]),
+                ("This week".to_string(), vec!["saturday"]),
+                ("Later".to_string(), vec!["next week"]),
+                ("No due date".to_string(), vec!["someday"]),
+            ]
+        );
+        assert_eq!(
+            summary(group_tasks(&tasks, Grouping::Project, now, zone, Weekday::Mon)),
+            [
+                ("+home".to_string(), vec!["overdue +home"]),
+                ("+work".to_string(), vec!["tonight +work", "tomorrow +work #call"]),
+                ("No project".to_string(), vec!["saturday", "next week", "someday"]),
+            ]
+        );
+        assert_eq!(group_tasks(&tasks, Grouping::Tag, now, zone, Weekday::Mon)[2].title, "#call");
+        // Weeks starting on Tuesday run until Monday the 19th.
+        let groups = group_tasks(&tasks, Grouping::Due, now, zone, Weekday::Tue);
+        assert_eq!(descriptions(groups[3].tasks.iter().copied()), ["saturday", "next week"]);
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use chrono::SecondsFormat;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Transaction, TransactionBehavior};
+
+use crate::merge::{Change, Conflict};
+use crate::storage;
+use crate::store::{FileStore, TaskStore};
+use crate::task::{Task, TaskId};
+
+/// The schema changes in order; a database's `user_version` is how many
+/// of them it has had applied.
+const MIGRATIONS: [&str; 1] = [
+    // Columns hold the fields of the task file format, with dates as RFC
+    // 3339 in UTC to the nanosecond so they sort as text.
+    "CREATE TABLE tasks (
+        id TEXT PRIMARY KEY NOT NULL,
+        description TEXT NOT NULL,
+        due_date TEXT,
+        completed_at TEXT,
+        priority TEXT,
+        tags TEXT,
+        lead_times TEXT,
+        recurrence TEXT,
+        reminder_fired_through TEXT,
+        reminder_pending TEXT,
+        snoozed_until TEXT,
+        created_at TEXT
+    );
+    CREATE INDEX tasks_due_date ON tasks (due_date);
+    CREATE INDEX tasks_status ON tasks (completed_at IS NOT NULL, due_date);
+    CREATE TABLE task_tags (
+        task_id TEXT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (task_id, tag)
+    );
+    CREATE INDEX task_tags_tag ON task_tags (tag);",
+];
+
+/// How long a save waits for another process to finish writing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Stores tasks in a SQLite database.
+///
+/// Saving only writes the tasks that changed since the last load or save,
+/// in a single transaction, so a failed save leaves the database as it was
+/// and large task lists stay cheap to update. Tasks load in the order they
+/// were first saved.
+///
+/// Other processes can use the database at the same time: changes they
+/// save to other tasks are kept. A task changed both here and elsewhere
+/// gets the version from here, and is reported to the
+/// [`on_warning`](SqliteStore::on_warning) callback.
+pub struct SqliteStore {
+    connection: Connection,
+    path: Option<PathBuf>,
+    on_warning: Option<fn(&str)>,
+    /// The tasks as last loaded or saved, to tell what a save changes.
+    saved: HashMap<TaskId, Task>,
+    /// SQLite's `data_version` then, which changes when others commit.
+    seen: Option<i64>,
+}
+
+impl SqliteStore {
+    /// Opens the database at `path`, creating it if needed, and brings its
+    /// schema up to date.
+    pub fn open<P: Into<PathBuf>>(path: P) -> io::Result<SqliteStore> {
+        let path = path.into();
+        let connection = Connection::open(&path).map_err(sql_error)?;
+        SqliteStore::with_connection(connection, Some(path))
+    }
+
+    /// A database that lives only as long as the store.
+    pub fn open_in_memory() -> io::Result<SqliteStore> {
+        SqliteStore::with_connection(Connection::open_in_memory().map_err(sql_error)?, None)
+    }
+
+    fn with_connection(mut connection: Connection, path: Option<PathBuf>) -> io::Result<SqliteStore> {
+        connection.pragma_update(None, "foreign_keys", true).map_err(sql_error)?;
+        connection.busy_timeout(BUSY_TIMEOUT).map_err(sql_error)?;
+        migrate(&mut connection)?;
+        Ok(SqliteStore { connection, path, on_warning: None, saved: HashMap::new(), seen: None })
+    }
+
+    /// Calls `report` for each task a save found was changed both here
+    /// and by another process.
+    pub fn on_warning(mut self, report: fn(&str)) -> Self {
+        self.on_warning = Some(report);
+        self
+    }
+
+    fn data_version(&self) -> io::Result<i64> {
+        self.connection.query_row("PRAGMA data_version", [], |row| row.get(0)).map_err(sql_error)
+    }
+
+    /// The schema version of the database.
+    pub fn schema_version(&self) -> io::Result<usize> {
+        user_version(&self.connection)
+    }
+}
+
+/// Opens the database at `path` like [`SqliteStore::open`]. If there is no
+/// database yet but there is a task file at `csv_path`, the new database
+/// starts out with its tasks, and their number is returned; the file itself
+/// is left as it was.
+pub fn open_migrating(path: &Path, csv_path: &Path) -> io::Result<(SqliteStore, Option<usize>)> {
+    let migrate = !path.exists() && csv_path.exists();
+    let mut store = SqliteStore::open(path)?;
+    if !migrate {
+        return Ok((store, None));
+    }
+    let tasks = FileStore::new(csv_path).load()?;
+    store.save(&tasks)?;
+    Ok((store, Some(tasks.len())))
+}
+
+impl TaskStore for SqliteStore {
+    fn load(&mut self) -> io::Result<Vec<Task>> {
+        let columns = storage::COLUMNS.join(", ");
+        let mut statement =
+            self.connection.prepare(&format!("SELECT {} FROM tasks ORDER BY rowid", columns)).map_err(sql_error)?;
+        let rows = statement
+            .query_map([], |row| {
+                (0..storage::COLUMNS.len()).map(|i| row.get::<_, Option<String>>(i)).collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(sql_error)?;
+        let tasks = rows.map(|row| parse_row(&row.map_err(sql_error)?)).collect::<io::Result<Vec<_>>>()?;
+        drop(statement);
+        self.saved = tasks.iter().map(|task| (task.id, task.clone())).collect();
+        self.seen = Some(self.data_version()?);
+        Ok(tasks)
+    }
+
+    fn save(&mut self, tasks: &[Task]) -> io::Result<()> {
+        let unchanged = self.seen == Some(self.data_version()?);
+        // Take the write lock up front so the rows compared below cannot
+        // change before they are written.
+        let transaction =
+            self.connection.transaction_with_behavior(TransactionBehavior::Immediate).map_err(sql_error)?;
+        let mut conflicts = Vec::new();
+        for task in tasks {
+            let before = self.saved.get(&task.id);
+            if before == Some(task) {
+                continue;
+            }
+            if let Some(before) = before {
+                match stored_task(&transaction, task.id)? {
+                    None => conflicts.push(Conflict::new(task, Change::Edited, Change::Deleted)),
+                    Some(current) if current != *before && current != *task => {
+                        conflicts.push(Conflict::new(task, Change::Edited, Change::Edited))
+                    }
+                    Some(_) => {}
+                }
+            }
+            write_task(&transaction, task).map_err(sql_error)?;
+        }
+        let kept: HashMap<TaskId, &Task> = tasks.iter().map(|task| (task.id, task)).collect();
+        for (id, before) in self.saved.iter().filter(|(id, _)| !kept.contains_key(id)) {
+            if stored_task(&transaction, *id)?.is_some_and(|current| current != *before) {
+                conflicts.push(Conflict::new(before, Change::Deleted, Change::Edited));
+            }
+            transaction.execute("DELETE FROM tasks WHERE id = ?1", [id.to_string()]).map_err(sql_error)?;
+        }
+        transaction.commit().map_err(sql_error)?;
+        if let Some(report) = self.on_warning {
+            for conflict in &conflicts {
+                report(&conflict.to_string());
+            }
+        }
+        self.saved = tasks.iter().map(|task| (task.id, task.clone())).collect();
+        // Others' changes that were kept still need loading.
+        self.seen = if unchanged { Some(self.data_version()?) } else { None };
+        Ok(())
+    }
+
+    fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn has_changed(&mut self) -> io::Result<bool> {
+        Ok(self.seen != Some(self.data_version()?))
+    }
+}
+
+/// Builds a task from the columns of a row, in [`storage::COLUMNS`] order.
+fn parse_row(row: &[Option<String>]) -> io::Result<Task> {
+    let field = |name: &str| {
+        let i = storage::COLUMNS.iter().position(|column| *column == name)?;
+        Some(row[i].as_deref().unwrap_or(""))
+    };
+    storage::parse_task(field).map_err(|e| {
+        let id = row[0].as_deref().unwrap_or("?");
+        io::Error::new(io::ErrorKind::InvalidData, format!("task {}: {}", id, e))
+    })
+}
+
+/// The task with `id` as the database has it now.
+fn stored_task(transaction: &Transaction<'_>, id: TaskId) -> io::Result<Option<Task>> {
+    let sql = format!("SELECT {} FROM tasks WHERE id = ?1", storage::COLUMNS.join(", "));
+    let row = transaction
+        .query_row(&sql, [id.to_string()], |row| {
+            (0..storage::COLUMNS.len()).map(|i| row.get::<_, Option<String>>(i)).collect::<Result<Vec<_>, _>>()
+        })
+        .optional()
+        .map_err(sql_error)?;
+    row.map(|row| parse_row(&row)).transpose()
+}
+
+/// Inserts `task`, or updates it in place so it keeps its position.
+fn write_task(transaction: &Transaction<'_>, task: &Task) -> rusqlite::Result<()> {
+    let record = storage::record(task, SecondsFormat::Nanos);
+    let values = record.iter().map(|value| Some(value.as_str()).filter(|value| !value.is_empty()));
+    let placeholders: Vec<String> = (1..=storage::COLUMNS.len()).map(|i| format!("?{}", i)).collect();
+    let updates: Vec<String> =
+        storage::COLUMNS[1..].iter().map(|column| format!("{} = excluded.{}", column, column)).collect();
+    transaction.execute(
+        &format!(
+            "INSERT INTO tasks ({}) VALUES ({}) ON CONFLICT (id) DO UPDATE SET {}",
+            storage::COLUMNS.join(", "),
+            placeholders.join(", "),
+            updates.join(", ")
+        ),
+        params_from_iter(values),
+    )?;
+    let id = task.id.to_string();
+    transaction.execute("DELETE FROM task_tags WHERE task_id = ?1", [&id])?;
+    for tag in &task.tags {
+        let sql = "INSERT OR IGNORE INTO task_tags (task_id, tag) VALUES (?1, ?2)";
+        transaction.execute(sql, params![id, tag.to_string()])?;
+    }
+    Ok(())
+}
+
+/// Applies the migrations the database has not had yet, each in its own
+/// transaction.
+fn migrate(connection: &mut Connection) -> io::Result<()> {
+    let version = user_version(connection)?;
+    if version > MIGRATIONS.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "database has schema version {}, but this build only understands up to {}",
+                version,
+                MIGRATIONS.len()
+            ),
+        ));
+    }
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction().map_err(sql_error)?;
+        transaction.execute_batch(migration).map_err(sql_error)?;
+        transaction.pragma_update(None, "user_version", i + 1).map_err(sql_error)?;
+        transaction.commit().map_err(sql_error)?;
+    }
+    Ok(())
+}
+
+fn user_version(connection: &Connection) -> io::Result<usize> {
+    let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(sql_error)?;
+    Ok(version as usize)
+}
+
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Priority;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn task(description: &str) -> Task {
+        let due = "2026-10-14T12:00:00.5Z".parse().unwrap();
+        Task::new(TaskId::generate(), description.to_string(), Some(due))
+    }
+
+    fn count(store: &SqliteStore, sql: &str) -> i64 {
+        store.connection.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn saves_changes_and_keeps_order() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+        let mut tasks = vec![task("Pay rent +home #urgent"), task("Call Bob +work"), task("Water plants +home")];
+        tasks[1].priority = Priority::High;
+        tasks[1].complete("2026-10-14T13:00:00Z".parse().unwrap());
+        store.save(&tasks).unwrap();
+        assert_eq!(store.load().unwrap(), tasks);
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM task_tags WHERE tag = '+home'"), 2);
+
+        tasks.remove(0);
+        tasks[1].tags.clear();
+        tasks[1].description = "Water plants".to_string();
+        tasks.push(task("New"));
+        store.save(&tasks).unwrap();
+        assert_eq!(store.load().unwrap(), tasks);
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM task_tags"), 1);
+
+        // A task another process added since the last load is kept.
+        let theirs = "INSERT INTO tasks (id, description) VALUES ('0123456789abcdef0123456789abcdef', 'Theirs')";
+        store.connection.execute(theirs, []).unwrap();
+        tasks[0].priority = Priority::Low;
+        store.save(&tasks).unwrap();
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.len(), 4);
+        assert_eq!(loaded[3].description, "Theirs");
+    }
+
+    #[test]
+    fn migrates_from_task_file() {
+        let dir = std::env::temp_dir().join(format!("todo-sqlite-{}", TaskId::generate()));
+        std::fs::create_dir(&dir).unwrap();
+        let (csv, db) = (dir.join("tasks.csv"), dir.join("tasks.db"));
+        let tasks = vec![task("Pay rent +home"), task("Call Bob")];
+        FileStore::new(&csv).save(&tasks).unwrap();
+
+        let (mut store, migrated) = open_migrating(&db, &csv).unwrap();
+        assert_eq!(migrated, Some(2));
+        store.save(&tasks[..1]).unwrap();
+        drop(store);
+        // Only a missing database is filled from the file.
+        let (mut store, migrated) = open_migrating(&db, &csv).unwrap();
+        assert_eq!((store.load().unwrap(), migrated), (tasks[..1].to_vec(), None));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_changes_from_other_connections() {
+        static CONFLICTS: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!("todo-sqlite-{}", TaskId::generate()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("tasks.db");
+        let open = || {
+            SqliteStore::open(&path).unwrap().on_warning(|_| {
+                CONFLICTS.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        let (mut first, mut second) = (open(), open());
+        first.save(&[task("a"), task("b")]).unwrap();
+        let mut ours = first.load().unwrap();
+        let mut theirs = second.load().unwrap();
+
+        theirs[1].priority = Priority::Low;
+        second.save(&theirs).unwrap();
+        assert!(first.has_changed().unwrap() && !second.has_changed().unwrap());
+        ours[0].priority = Priority::High;
+        first.save(&ours).unwrap();
+        assert!(first.has_changed().unwrap());
+        let merged = first.load().unwrap();
+        assert_eq!((merged[0].priority, merged[1].priority), (Priority::High, Priority::Low));
+        assert_eq!(CONFLICTS.load(Ordering::SeqCst), 0);
+
+        let mut theirs = second.load().unwrap();
+        theirs[0].description = "a, second".to_string();
+        second.save(&theirs).unwrap();
+        let mut latest = merged;
+        latest[0].description = "a, first".to_string();
+        first.save(&latest).unwrap();
+        assert_eq!(CONFLICTS.load(Ordering::SeqCst), 1);
+        assert_eq!(second.load().unwrap()[0].description, "a, first");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_newer_schema() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        connection.pragma_update(None, "user_version", MIGRATIONS.len() + 1).unwrap();
+        let error = migrate(&mut connection).unwrap_err();
+        assert!(error.to_string().contains("only understands up to"));
+    }
+}
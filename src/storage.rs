@@ -0,0 +1,476 @@
+use std::io::{self, Read, Write};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+
+use crate::dates;
+use crate::tags::{self, Tag};
+use crate::task::{self, Task, TaskId};
+
+/// Version of the on-disk task format written by `write_tasks`.
+pub const FORMAT_VERSION: u32 = 1;
+
+const HEADER_PREFIX: &str = "# todo_reminder tasks v";
+/// Starts the last line, which gives the number of tasks so a file that
+/// was cut short can be told apart from a shorter list.
+const TRAILER_PREFIX: &str = "# end of tasks: ";
+pub(crate) const COLUMNS: [&str; 12] = [
+    "id",
+    "description",
+    "due_date",
+    "completed_at",
+    "priority",
+    "tags",
+    "lead_times",
+    "recurrence",
+    "reminder_fired_through",
+    "reminder_pending",
+    "snoozed_until",
+    "created_at",
+];
+
+/// Writes `tasks` in the versioned, CSV-quoted task format.
+pub fn write_tasks<W: Write>(mut writer: W, tasks: &[Task]) -> io::Result<()> {
+    writeln!(writer, "{}{}", HEADER_PREFIX, FORMAT_VERSION)?;
+    write_record(&mut writer, &COLUMNS)?;
+    for task in tasks {
+        write_record(&mut writer, &record(task, SecondsFormat::AutoSi))?;
+    }
+    writeln!(writer, "{}{}", TRAILER_PREFIX, tasks.len())?;
+    writer.flush()
+}
+
+/// The fields of `task` in [`COLUMNS`] order, as `write_tasks` stores them
+/// but with dates in `date_format`. Absent values are empty.
+pub(crate) fn record(task: &Task, date_format: SecondsFormat) -> [String; 12] {
+    let date = |date: Option<DateTime<Utc>>| {
+        date.map(|date| date.to_rfc3339_opts(date_format, true)).unwrap_or_default()
+    };
+    [
+        task.id.to_string(),
+        task.description.clone(),
+        date(task.due_date),
+        date(task.completed_at),
+        task.priority.to_string(),
+        format_tags(&task.tags),
+        format_lead_times(&task.lead_times),
+        task.recurrence.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+        date(task.reminder.fired_through),
+        if task.reminder.pending { "1" } else { "" }.to_string(),
+        date(task.reminder.snoozed_until),
+        date(task.created_at),
+    ]
+}
+
+/// Reads tasks written by `write_tasks`.
+///
+/// Files without a version header are treated as the legacy
+/// `description[,due date]` format so existing task lists are not lost.
+pub fn read_tasks<R: Read>(mut reader: R) -> io::Result<Vec<Task>> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+
+    let (first_line, rest) = match input.find('\n') {
+        Some(end) => (&input[..end], &input[end + 1..]),
+        None => (input.as_str(), ""),
+    };
+    let first_line = first_line.strip_suffix('\r').unwrap_or(first_line);
+
+    match first_line.strip_prefix(HEADER_PREFIX) {
+        Some(version) => {
+            let version: u32 = version
+                .trim()
+                .parse()
+                .map_err(|_| invalid_data(format!("line 1: invalid format version '{}'", version)))?;
+            if version != FORMAT_VERSION {
+                return Err(invalid_data(format!(
+                    "task file has format version {}, but this build only understands version {}",
+                    version, FORMAT_VERSION
+                )));
+            }
+            let (rest, expected) = split_trailer(rest).ok_or_else(|| {
+                invalid_data("the end of the file is missing, so it was probably only partly written".to_string())
+            })?;
+            let tasks = read_versioned(rest)?;
+            if tasks.len() != expected {
+                return Err(invalid_data(format!(
+                    "the file holds {} tasks but says it should have {}, so it was probably only partly written",
+                    tasks.len(),
+                    expected
+                )));
+            }
+            Ok(tasks)
+        }
+        None => Ok(read_legacy(&input)),
+    }
+}
+
+/// Writes one CSV record, quoting fields where needed.
+pub fn write_record<W: Write, S: AsRef<str>>(writer: &mut W, fields: &[S]) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        writer.write_all(quote_field(field.as_ref()).as_bytes())?;
+    }
+    writer.write_all(b"\n")
+}
+
+/// Quotes a single CSV field if it contains a separator, a quote, a line
+/// break or another control character.
+pub fn quote_field(field: &str) -> String {
+    if field.contains(|c: char| c == ',' || c == '"' || c.is_control()) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Splits the trailer line off the end of `input`, returning the records
+/// before it and the task count it gives.
+fn split_trailer(input: &str) -> Option<(&str, usize)> {
+    let body = input.trim_end();
+    let start = body.rfind('\n').map_or(0, |i| i + 1);
+    let count = body[start..].strip_prefix(TRAILER_PREFIX)?.trim().parse().ok()?;
+    Some((&input[..start], count))
+}
+
+fn read_versioned(input: &str) -> io::Result<Vec<Task>> {
+    // The version header is line 1, so records start on line 2.
+    let mut records = parse_records(input, 2)?.into_iter();
+
+    let header = match records.next() {
+        Some((_, header)) => header,
+        None => return Ok(Vec::new()),
+    };
+    let column = |name: &str| header.iter().position(|c| c == name);
+    if let Some(missing) = COLUMNS.iter().find(|name| column(name).is_none()) {
+        return Err(invalid_data(format!("line 2: missing '{}' column", missing)));
+    }
+
+    let mut tasks = Vec::new();
+    for (line, fields) in records {
+        if fields.len() != header.len() {
+            return Err(invalid_data(format!(
+                "line {}: expected {} fields, found {}",
+                line,
+                header.len(),
+                fields.len()
+            )));
+        }
+        let field = |name: &str| column(name).map(|i| fields[i].as_str());
+        let task = parse_task(field).map_err(|e| invalid_data(format!("line {}: {}", line, e)))?;
+        if task::position(&tasks, task.id).is_some() {
+            return Err(invalid_data(format!("line {}: duplicate task id {}", line, task.id)));
+        }
+        tasks.push(task);
+    }
+
+    Ok(tasks)
+}
+
+/// Builds a task from its stored fields, looked up by column name with
+/// `field`. Empty fields are absent values.
+pub(crate) fn parse_task<'a, F: Fn(&str) -> Option<&'a str>>(field: F) -> Result<Task, String> {
+    let date_field = |name: &str, what: &str| match field(name) {
+        Some("") | None => Ok(None),
+        Some(value) => parse_date(value).map(Some).ok_or_else(|| format!("invalid {} '{}'", what, value)),
+    };
+    let id = field("id").ok_or("missing id")?;
+    let id = id.parse::<TaskId>().map_err(|_| format!("invalid task id '{}'", id))?;
+    let description = field("description").ok_or("missing description")?;
+    let mut task = Task::new(id, description.to_string(), date_field("due_date", "due date")?);
+    task.created_at = date_field("created_at", "creation date")?;
+    task.completed_at = date_field("completed_at", "completion date")?;
+    if let Some(value) = field("priority").filter(|value| !value.is_empty()) {
+        task.priority = value.parse().map_err(|_| format!("invalid priority '{}'", value))?;
+    }
+    if let Some(value) = field("tags") {
+        task.tags = tags::parse_tag_list(value).map_err(|e| e.to_string())?;
+    }
+    if let Some(value) = field("lead_times").filter(|value| !value.is_empty()) {
+        task.lead_times =
+            dates::parse_lead_times(value).map_err(|_| format!("invalid reminder lead times '{}'", value))?;
+    }
+    task.recurrence = match field("recurrence") {
+        Some("") | None => None,
+        Some(value) => Some(value.parse().map_err(|e| format!("invalid recurrence '{}': {}", value, e))?),
+    };
+    task.reminder.fired_through = date_field("reminder_fired_through", "reminder time")?;
+    task.reminder.snoozed_until = date_field("snoozed_until", "snooze time")?;
+    task.reminder.pending = match field("reminder_pending") {
+        Some("") | None => false,
+        Some("1") => true,
+        Some(value) => return Err(format!("invalid reminder flag '{}'", value)),
+    };
+    Ok(task)
+}
+
+fn read_legacy(input: &str) -> Vec<Task> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let id = TaskId::generate();
+            // Old files wrote either "description" or "description,due date".
+            let task = match line.rsplit_once(',') {
+                Some((description, due_date)) => match due_date.parse::<DateTime<Utc>>() {
+                    Ok(date) => Task::new(id, description.to_string(), Some(date)),
+                    Err(_) => Task::new(id, line.to_string(), None),
+                },
+                None => Task::new(id, line.to_string(), None),
+            };
+            // They did not record when tasks were added.
+            Task { created_at: None, ..task }
+        })
+        .collect()
+}
+
+/// Splits `input` into CSV records, honouring quoted fields that contain
+/// separators, quotes or line breaks. Blank lines are skipped.
+fn parse_records(input: &str, first_line: usize) -> io::Result<Vec<(usize, Vec<String>)>> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = first_line;
+    let mut record_line = line;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            '"' => return Err(invalid_data(format!("line {}: unexpected quote in unquoted field", line))),
+            ',' => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                if !(fields.len() == 1 && fields[0].is_empty()) {
+                    records.push((record_line, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(invalid_data(format!("line {}: unterminated quoted field", record_line)));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((record_line, fields));
+    }
+
+    Ok(records)
+}
+
+fn format_tags(tags: &[Tag]) -> String {
+    let formatted: Vec<String> = tags.iter().map(Tag::to_string).collect();
+    formatted.join(" ")
+}
+
+fn format_lead_times(lead_times: &[Duration]) -> String {
+    if lead_times.is_empty() {
+        return "none".to_string();
+    }
+    let formatted: Vec<String> = lead_times.iter().map(|&lead_time| dates::format_lead_time(lead_time)).collect();
+    formatted.join(",")
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recurrence::{ByDay, Frequency, Recurrence};
+    use crate::reminder::ReminderState;
+    use crate::tags::TagKind;
+    use crate::task::Priority;
+    use chrono::Weekday;
+    use chrono::TimeZone;
+    use proptest::prelude::*;
+    use uuid::Uuid;
+
+    fn id(n: u128) -> TaskId {
+        TaskId(Uuid::from_u128(n))
+    }
+
+    /// Drops the ids, which are freshly generated when reading old files.
+    fn without_ids(tasks: Vec<Task>) -> Vec<(String, Option<DateTime<Utc>>)> {
+        tasks.into_iter().map(|task| (task.description, task.due_date)).collect()
+    }
+
+    fn roundtrip(tasks: &[Task]) -> Vec<Task> {
+        let mut buffer = Vec::new();
+        write_tasks(&mut buffer, tasks).unwrap();
+        read_tasks(buffer.as_slice()).unwrap()
+    }
+
+    fn arb_date() -> impl Strategy<Value = DateTime<Utc>> {
+        // Years 0001..=9999, the range RFC 3339 can represent.
+        (-62_135_596_800i64..=253_402_300_799, 0u32..1_000_000_000)
+            .prop_map(|(secs, nanos)| Utc.timestamp_opt(secs, nanos).unwrap())
+    }
+
+    fn arb_weekday() -> impl Strategy<Value = Weekday> {
+        proptest::sample::select(vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ])
+    }
+
+    fn arb_recurrence() -> impl Strategy<Value = Recurrence> {
+        // Only rules that validate; RRULE end dates have whole seconds.
+        let by_rules = prop_oneof![
+            Just((Frequency::Daily, vec![], vec![])),
+            Just((Frequency::Yearly, vec![], vec![])),
+            proptest::collection::vec(arb_weekday(), 0..3).prop_map(|days| {
+                (Frequency::Weekly, days.into_iter().map(|weekday| ByDay { ordinal: None, weekday }).collect(), vec![])
+            }),
+            (proptest::collection::vec((prop_oneof![-5i32..=-1, 1i32..=5], arb_weekday()), 0..3), proptest::collection::vec(prop_oneof![-31i32..=-1, 1i32..=31], 0..3))
+                .prop_map(|(days, month_days)| {
+                    let days = days.into_iter().map(|(ordinal, weekday)| ByDay { ordinal: Some(ordinal), weekday }).collect();
+                    (Frequency::Monthly, days, month_days)
+                }),
+        ];
+        let end = prop_oneof![
+            Just((None, None)),
+            (1u32..1000).prop_map(|count| (Some(count), None)),
+            (0i64..4_102_444_800).prop_map(|secs| (None, Some(Utc.timestamp_opt(secs, 0).unwrap()))),
+        ];
+        (by_rules, 1u32..10, end).prop_map(|((frequency, by_day, by_month_day), interval, (count, until))| Recurrence {
+            frequency,
+            interval,
+            by_day,
+            by_month_day,
+            until,
+            count,
+        })
+    }
+
+    fn arb_task() -> impl Strategy<Value = Task> {
+        let description = prop_oneof![
+            any::<String>(),
+            "[a-z ,\"\r\n]*",
+        ];
+        let reminder = (proptest::option::of(arb_date()), any::<bool>(), proptest::option::of(arb_date()))
+            .prop_map(|(fired_through, pending, snoozed_until)| ReminderState { fired_through, pending, snoozed_until });
+        // Lead times are whole minutes, stored latest-first without repeats.
+        let lead_times = proptest::collection::btree_set(0i64..1_000_000, 0..4)
+            .prop_map(|minutes| minutes.into_iter().rev().map(Duration::minutes).collect::<Vec<_>>());
+        let dates = (proptest::option::of(arb_date()), proptest::option::of(arb_date()), proptest::option::of(arb_date()));
+        let priority = proptest::sample::select(Priority::ALL.to_vec());
+        let kind = proptest::sample::select(vec![TagKind::Project, TagKind::Context, TagKind::Label]);
+        let tags = proptest::collection::btree_set((kind, "[a-z][a-z0-9./_-]{0,8}"), 0..4)
+            .prop_map(|tags| tags.into_iter().map(|(kind, name)| Tag::new(kind, &name)).collect::<Vec<_>>());
+        (description, dates, (priority, tags), lead_times, proptest::option::of(arb_recurrence()), reminder)
+            .prop_map(|(description, (due_date, created_at, completed_at), (priority, tags), lead_times, recurrence, reminder)| Task {
+                created_at,
+                completed_at,
+                priority,
+                tags,
+                lead_times,
+                recurrence,
+                reminder,
+                ..Task::new(id(0), description, due_date)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn load_of_save_is_identity(tasks in proptest::collection::btree_map(any::<u128>(), arb_task(), 0..16)) {
+            // Keying by id keeps the generated ids unique.
+            let tasks: Vec<Task> = tasks.into_iter().map(|(n, task)| Task { id: id(n), ..task }).collect();
+            prop_assert_eq!(roundtrip(&tasks), tasks);
+        }
+    }
+
+    #[test]
+    fn undated_and_comma_tasks_survive() {
+        let tasks = vec![
+            Task::new(id(1), "Grocery".to_string(), None),
+            Task::new(id(2), "milk, eggs, \"bread\"".to_string(), None),
+            Task::new(id(7), String::new(), None),
+            Task::new(TaskId::generate(), "call\nmum".to_string(), Some(Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 0).unwrap())),
+        ];
+        assert_eq!(roundtrip(&tasks), tasks);
+    }
+
+    #[test]
+    fn reads_legacy_format() {
+        let legacy = "run\nshopping,2026-11-01 09:00:00 UTC\n\n";
+        let tasks = read_tasks(legacy.as_bytes()).unwrap();
+        assert_eq!(
+            without_ids(tasks),
+            vec![
+                ("run".to_string(), None),
+                ("shopping".to_string(), Some(Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 0).unwrap())),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_ids_and_missing_columns() {
+        let mut written = Vec::new();
+        write_tasks(&mut written, &[Task::new(id(4), "a".to_string(), None), Task::new(id(4), "b".to_string(), None)])
+            .unwrap();
+        assert!(read_tasks(written.as_slice()).unwrap_err().to_string().contains("duplicate task id"));
+        let input = format!("{}{}\nid,description\n# end of tasks: 0\n", HEADER_PREFIX, FORMAT_VERSION);
+        assert!(read_tasks(input.as_bytes()).unwrap_err().to_string().contains("missing 'due_date' column"));
+    }
+
+    #[test]
+    fn detects_partly_written_files() {
+        let tasks = [Task::new(id(1), "a".to_string(), None), Task::new(id(2), "b, \"c\"".to_string(), None)];
+        let mut written = Vec::new();
+        write_tasks(&mut written, &tasks).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.ends_with("\n# end of tasks: 2\n"));
+        // Cut after the first task, in the middle of the second and before
+        // the end marker.
+        let second = written.find(&id(2).to_string()).unwrap();
+        let trailer = written.rfind("# end").unwrap();
+        for end in [second, second + 40, trailer] {
+            let error = read_tasks(&written.as_bytes()[..end]).unwrap_err();
+            assert!(error.to_string().contains("partly written"), "{}", error);
+        }
+        let edited = written.replace("# end of tasks: 2", "# end of tasks: 3");
+        assert!(read_tasks(edited.as_bytes()).unwrap_err().to_string().contains("holds 2 tasks"));
+    }
+
+    #[test]
+    fn rejects_newer_format_version() {
+        let input = format!("{}{}\ndescription,due_date\n", HEADER_PREFIX, FORMAT_VERSION + 1);
+        assert!(read_tasks(input.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,389 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::merge;
+use crate::scheduler::{lock, SharedTasks};
+use crate::storage;
+use crate::task::Task;
+
+/// Somewhere tasks can be loaded from and saved back to.
+pub trait TaskStore {
+    fn load(&mut self) -> io::Result<Vec<Task>>;
+    fn save(&mut self, tasks: &[Task]) -> io::Result<()>;
+
+    /// The file backing the store, if any, so it can be watched for changes.
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Whether the stored tasks may have changed since this store last
+    /// loaded or saved them, for instance because another process saved.
+    fn has_changed(&mut self) -> io::Result<bool> {
+        Ok(false)
+    }
+}
+
+impl<S: TaskStore + ?Sized> TaskStore for Box<S> {
+    fn load(&mut self) -> io::Result<Vec<Task>> {
+        (**self).load()
+    }
+
+    fn save(&mut self, tasks: &[Task]) -> io::Result<()> {
+        (**self).save(tasks)
+    }
+
+    fn path(&self) -> Option<&Path> {
+        (**self).path()
+    }
+
+    fn has_changed(&mut self) -> io::Result<bool> {
+        (**self).has_changed()
+    }
+}
+
+/// Brings the shared `tasks` and `store` in line. `saved` is the list as
+/// last loaded or saved: if `tasks` differ from it they are saved, and if
+/// the store then reports changes from elsewhere the tasks are loaded
+/// again. Returns whether they were.
+pub fn sync<S: TaskStore + ?Sized>(store: &mut S, tasks: &SharedTasks, saved: &mut Vec<Task>) -> io::Result<bool> {
+    let mut tasks = lock(tasks);
+    if *tasks != *saved {
+        store.save(&tasks)?;
+        saved.clone_from(&tasks);
+    }
+    if !store.has_changed()? {
+        return Ok(false);
+    }
+    *tasks = store.load()?;
+    saved.clone_from(&tasks);
+    Ok(true)
+}
+
+/// How many earlier versions of the task file [`FileStore`] keeps unless
+/// told otherwise.
+pub const DEFAULT_BACKUPS: usize = 3;
+
+/// Stores tasks in a file using the format from [`storage`].
+///
+/// Saves never leave a half-written file behind: the tasks are written to
+/// a temporary file beside it, flushed to disk and then renamed over the
+/// old one. The versions being replaced are kept as `FILE.1` (the most
+/// recent) to `FILE.N`. If the file is nevertheless found damaged on load,
+/// for instance because another program cut it short, the newest readable
+/// backup is loaded instead and the damaged file moved to `FILE.damaged`.
+///
+/// Several processes can share the file. Each load and save holds an
+/// advisory lock on `FILE.lock`, and a save first reads the file again and
+/// merges in whatever other processes saved since this store last loaded
+/// or saved, task by task; see [`merge::merge`].
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    path: PathBuf,
+    backups: usize,
+    on_warning: Option<fn(&str)>,
+    /// The tasks as last loaded or saved, which changes are merged from.
+    base: Option<Vec<Task>>,
+    /// The file's modification time and size after that.
+    seen: Option<Stamp>,
+}
+
+type Stamp = (SystemTime, u64);
+
+impl FileStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        FileStore { path: path.into(), backups: DEFAULT_BACKUPS, on_warning: None, base: None, seen: None }
+    }
+
+    /// Keeps `count` earlier versions of the file, or none at all for 0.
+    pub fn with_backups(mut self, count: usize) -> Self {
+        self.backups = count;
+        self
+    }
+
+    /// Calls `report` with an explanation whenever a damaged file is
+    /// replaced by a backup, and for each task a save found was changed
+    /// both by this store's user and by another process.
+    pub fn on_warning(mut self, report: fn(&str)) -> Self {
+        self.on_warning = Some(report);
+        self
+    }
+
+    fn warn(&self, message: &str) {
+        if let Some(report) = self.on_warning {
+            report(message);
+        }
+    }
+
+    /// Takes the advisory lock, which is held until the file is dropped.
+    fn lock(&self) -> io::Result<File> {
+        let file = OpenOptions::new().write(true).create(true).truncate(false).open(suffixed(&self.path, "lock"))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// Reads the file, recovering from damage. A missing file is an empty
+    /// task list.
+    fn read(&self) -> io::Result<Vec<Task>> {
+        match read_file(&self.path) {
+            Ok(tasks) => Ok(tasks.unwrap_or_default()),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => self.recover(e),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&self, tasks: &[Task]) -> io::Result<()> {
+        let temporary = suffixed(&self.path, "tmp");
+        let file = OpenOptions::new().write(true).create(true).truncate(true).open(&temporary)?;
+        let mut writer = BufWriter::new(file);
+        storage::write_tasks(&mut writer, tasks)?;
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        self.rotate_backups()?;
+        fs::rename(&temporary, &self.path)?;
+        sync_parent(&self.path)
+    }
+
+    /// Where the `n`th most recent earlier version is kept, from 1.
+    pub fn backup_path(&self, n: usize) -> PathBuf {
+        suffixed(&self.path, &n.to_string())
+    }
+
+    /// Replaces a damaged file with the newest backup that can be read.
+    fn recover(&self, damage: io::Error) -> io::Result<Vec<Task>> {
+        let name = self.path.display();
+        for n in 1..=self.backups {
+            let backup = self.backup_path(n);
+            let tasks = match read_file(&backup) {
+                Ok(Some(tasks)) => tasks,
+                Ok(None) | Err(_) => continue,
+            };
+            let damaged = suffixed(&self.path, "damaged");
+            fs::rename(&self.path, &damaged)?;
+            copy_durably(&backup, &self.path)?;
+            self.warn(&format!(
+                "{} is damaged ({}), so its last good version, {}, was restored; the damaged file was moved to {}",
+                name,
+                damage,
+                backup.display(),
+                damaged.display()
+            ));
+            return Ok(tasks);
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is damaged ({}) and there is no readable backup of it", name, damage),
+        ))
+    }
+
+    /// Moves each backup one place down, dropping the oldest, and keeps the
+    /// current file as the first.
+    fn rotate_backups(&self) -> io::Result<()> {
+        if self.backups == 0 || !self.path.exists() {
+            return Ok(());
+        }
+        for n in (1..self.backups).rev() {
+            match fs::rename(self.backup_path(n), self.backup_path(n + 1)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        // A hard link keeps the current file in place until the new one
+        // replaces it.
+        let first = self.backup_path(1);
+        match fs::remove_file(&first) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        if fs::hard_link(&self.path, &first).is_err() {
+            fs::copy(&self.path, &first)?;
+        }
+        Ok(())
+    }
+}
+
+impl TaskStore for FileStore {
+    /// A missing file is treated as an empty task list.
+    fn load(&mut self) -> io::Result<Vec<Task>> {
+        let _lock = self.lock()?;
+        let tasks = self.read()?;
+        self.base = Some(tasks.clone());
+        self.seen = stamp(&self.path)?;
+        Ok(tasks)
+    }
+
+    /// Merges `tasks` with the file as other processes left it. Without an
+    /// earlier load, `tasks` replace the file's contents.
+    fn save(&mut self, tasks: &[Task]) -> io::Result<()> {
+        let _lock = self.lock()?;
+        let theirs = self.read()?;
+        let base = self.base.as_deref().unwrap_or(&theirs);
+        let merged = merge::merge(base, tasks, &theirs);
+        self.write(&merged.tasks)?;
+        for conflict in &merged.conflicts {
+            self.warn(&conflict.to_string());
+        }
+        // The caller does not have what was merged in yet, so it should
+        // load again.
+        self.seen = if merged.tasks == tasks { stamp(&self.path)? } else { None };
+        self.base = Some(tasks.to_vec());
+        Ok(())
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn has_changed(&mut self) -> io::Result<bool> {
+        Ok(stamp(&self.path)? != self.seen)
+    }
+}
+
+/// Reads the tasks in the file at `path`, or `None` if there is no file.
+fn read_file(path: &Path) -> io::Result<Option<Vec<Task>>> {
+    match OpenOptions::new().read(true).open(path) {
+        Ok(file) => storage::read_tasks(BufReader::new(file)).map(Some),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The modification time and size of the file at `path`, or `None` if
+/// there is no file.
+fn stamp(path: &Path) -> io::Result<Option<Stamp>> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(Some((metadata.modified()?, metadata.len()))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Copies `from` over `to` the same way [`FileStore`] saves.
+fn copy_durably(from: &Path, to: &Path) -> io::Result<()> {
+    let temporary = suffixed(to, "tmp");
+    fs::copy(from, &temporary)?;
+    File::open(&temporary)?.sync_all()?;
+    fs::rename(&temporary, to)?;
+    sync_parent(to)
+}
+
+/// Flushes the directory holding `path`, so a rename into it survives a
+/// crash. Not every platform can open directories; there the rename is
+/// left to the operating system.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    match File::open(dir) {
+        Ok(dir) => dir.sync_all(),
+        Err(_) => Ok(()),
+    }
+}
+
+/// `path` with `.suffix` added to its file name.
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Keeps tasks in memory only; useful for tests and embedding.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    pub tasks: Vec<Task>,
+}
+
+impl TaskStore for MemoryStore {
+    fn load(&mut self) -> io::Result<Vec<Task>> {
+        Ok(self.tasks.clone())
+    }
+
+    fn save(&mut self, tasks: &[Task]) -> io::Result<()> {
+        self.tasks = tasks.to_vec();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Priority, TaskId};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn tasks(descriptions: &[&str]) -> Vec<Task> {
+        descriptions.iter().map(|d| Task::new(TaskId::generate(), d.to_string(), None)).collect()
+    }
+
+    #[test]
+    fn keeps_backups_and_recovers_from_damage() {
+        let dir = std::env::temp_dir().join(format!("todo-store-{}", TaskId::generate()));
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("tasks.csv");
+        let mut store = FileStore::new(&path).with_backups(2);
+        let versions = [tasks(&["a"]), tasks(&["a", "b"]), tasks(&["a", "b", "c"]), tasks(&["d"])];
+        for version in &versions {
+            store.save(version).unwrap();
+        }
+        assert_eq!(store.load().unwrap(), versions[3]);
+        assert_eq!(FileStore::new(store.backup_path(1)).load().unwrap(), versions[2]);
+        assert_eq!(FileStore::new(store.backup_path(2)).load().unwrap(), versions[1]);
+        assert!(!store.backup_path(3).exists());
+        assert!(!suffixed(&path, "tmp").exists());
+
+        // A file cut short is replaced by the newest backup.
+        let written = fs::read(&path).unwrap();
+        fs::write(&path, &written[..written.len() - 5]).unwrap();
+        let mut store = store.on_warning(|message| assert!(message.contains("was restored"), "{}", message));
+        assert_eq!(store.load().unwrap(), versions[2]);
+        assert_eq!(fs::read(suffixed(&path, "damaged")).unwrap(), written[..written.len() - 5]);
+
+        fs::write(&path, "# todo_reminder tasks v1\nid,description\n").unwrap();
+        let mut store = FileStore::new(&path).with_backups(0);
+        assert!(store.load().unwrap_err().to_string().contains("no readable backup"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn merges_saves_from_other_processes() {
+        static CONFLICTS: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!("todo-store-{}", TaskId::generate()));
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("tasks.csv");
+        let open = || {
+            FileStore::new(&path).on_warning(|_| {
+                CONFLICTS.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        open().save(&tasks(&["a", "b"])).unwrap();
+
+        let (mut first, mut second) = (open(), open());
+        let mut ours = first.load().unwrap();
+        let mut theirs = second.load().unwrap();
+        ours[0].priority = Priority::High;
+        first.save(&ours).unwrap();
+        assert!(!first.has_changed().unwrap());
+        assert!(second.has_changed().unwrap());
+        theirs[1].priority = Priority::Low;
+        theirs.push(tasks(&["c"]).remove(0));
+        second.save(&theirs).unwrap();
+        // The second store merged in the first one's change, which its
+        // caller has yet to load.
+        assert!(second.has_changed().unwrap());
+        let merged = second.load().unwrap();
+        let priorities: Vec<Priority> = merged.iter().map(|task| task.priority).collect();
+        assert_eq!(priorities, [Priority::High, Priority::Low, Priority::Normal]);
+        assert_eq!(CONFLICTS.load(Ordering::SeqCst), 0);
+
+        // Both edit the same task: the later save wins and reports it.
+        ours[0].description = "a, first".to_string();
+        first.save(&ours).unwrap();
+        let mut latest = merged;
+        latest[0].description = "a, second".to_string();
+        second.save(&latest).unwrap();
+        assert_eq!(CONFLICTS.load(Ordering::SeqCst), 1);
+        assert_eq!(first.load().unwrap()[0].description, "a, second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
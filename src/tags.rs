@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::task::Task;
+
+/// What a tag groups tasks by, told apart by its sigil as in todo.txt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TagKind {
+    /// `+project`
+    Project,
+    /// `@context`
+    Context,
+    /// `#tag`
+    Label,
+}
+
+impl TagKind {
+    pub fn sigil(self) -> char {
+        match self {
+            TagKind::Project => '+',
+            TagKind::Context => '@',
+            TagKind::Label => '#',
+        }
+    }
+
+    fn from_sigil(c: char) -> Option<TagKind> {
+        match c {
+            '+' => Some(TagKind::Project),
+            '@' => Some(TagKind::Context),
+            '#' => Some(TagKind::Label),
+            _ => None,
+        }
+    }
+}
+
+/// A project, context or plain tag. Names are kept in lower case so
+/// `+Work` and `+work` are the same project.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tag {
+    pub kind: TagKind,
+    pub name: String,
+}
+
+impl Tag {
+    pub fn new(kind: TagKind, name: &str) -> Tag {
+        Tag { kind, name: name.to_lowercase() }
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.kind.sigil(), self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseTagError(String);
+
+impl fmt::Display for ParseTagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid tag '{}'; write +project, @context or #tag", self.0)
+    }
+}
+
+impl Error for ParseTagError {}
+
+/// Parses a single tag written with its sigil, e.g. `+work`.
+impl FromStr for Tag {
+    type Err = ParseTagError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_word(s.trim()).ok_or_else(|| ParseTagError(s.trim().to_string()))
+    }
+}
+
+/// Returns the tags mentioned in `description`, e.g. `+garden` and `@home`
+/// in "Mow the lawn +garden @home", sorted and without repeats. Words that
+/// merely start with a sigil, like "+1" or an e-mail address, are skipped.
+pub fn parse_tags(description: &str) -> Vec<Tag> {
+    let mut tags: Vec<Tag> = description
+        .split_whitespace()
+        .filter_map(|word| parse_word(word.trim_end_matches(['.', ',', ';', ':', '!', '?', ')'])))
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Parses tags listed explicitly, separated by commas or spaces, such as
+/// `+work, @office`.
+pub fn parse_tag_list(input: &str) -> Result<Vec<Tag>, ParseTagError> {
+    let mut tags = input
+        .split([',', ' '])
+        .filter(|word| !word.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<Tag>, _>>()?;
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
+}
+
+/// Returns true if `task` carries every tag in `tags`.
+pub fn has_all(task: &Task, tags: &[Tag]) -> bool {
+    tags.iter().all(|tag| task.tags.contains(tag))
+}
+
+/// Counts the open tasks carrying each tag used in `tasks`. Tags only
+/// found on completed tasks are listed with a count of zero.
+pub fn open_counts(tasks: &[Task]) -> Vec<(Tag, usize)> {
+    let mut counts: BTreeMap<Tag, usize> = BTreeMap::new();
+    for task in tasks {
+        for tag in &task.tags {
+            *counts.entry(tag.clone()).or_default() += usize::from(!task.is_completed());
+        }
+    }
+    counts.into_iter().collect()
+}
+
+fn parse_word(word: &str) -> Option<Tag> {
+    let mut chars = word.chars();
+    let kind = TagKind::from_sigil(chars.next()?)?;
+    let name = chars.as_str();
+    let valid = name.starts_with(char::is_alphabetic)
+        && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+    if valid {
+        Some(Tag::new(kind, name))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(text: &str) -> Tag {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn finds_tags_in_descriptions() {
+        assert_eq!(
+            parse_tags("Call Bob @phone about +Garden.Shed, #urgent! (+1 on email bob@example.com)"),
+            vec![tag("+garden.shed"), tag("@phone"), tag("#urgent")]
+        );
+        assert_eq!(parse_tags("plain text"), vec![]);
+    }
+
+    #[test]
+    fn parses_explicit_lists() {
+        assert_eq!(parse_tag_list("@home, +work +work").unwrap(), vec![tag("+work"), tag("@home")]);
+        assert!(parse_tag_list("work").is_err());
+    }
+}
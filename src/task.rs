@@ -0,0 +1,356 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::dates::Zone;
+use crate::recurrence::Recurrence;
+use crate::reminder::{self, ReminderState};
+use crate::tags::{self, Tag};
+
+/// Number of hex digits shown for a task id in listings.
+pub const SHORT_ID_LEN: usize = 8;
+
+/// Identifies a task for as long as it exists, independent of its position
+/// in the list. Ids are random 128-bit values, so tasks created in different
+/// lists or by different programs never collide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaskId(pub(crate) Uuid);
+
+impl TaskId {
+    pub fn generate() -> TaskId {
+        TaskId(Uuid::new_v4())
+    }
+
+    /// The leading digits of the id, which is how listings show it.
+    pub fn short(&self) -> String {
+        let mut id = self.to_string();
+        id.truncate(SHORT_ID_LEN);
+        id
+    }
+}
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.simple())
+    }
+}
+
+impl FromStr for TaskId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(TaskId)
+    }
+}
+
+/// Why a task id typed by the user did not select exactly one task.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LookupError {
+    NotFound(String),
+    Ambiguous(String, Vec<TaskId>),
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupError::NotFound(query) => write!(f, "no task with id '{}'", query),
+            LookupError::Ambiguous(query, ids) => {
+                write!(f, "id '{}' is ambiguous; it matches", query)?;
+                for id in ids {
+                    write!(f, " {}", id)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for LookupError {}
+
+/// How important a task is. Orders from least to most urgent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [Priority::Low, Priority::Normal, Priority::High, Priority::Urgent];
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        };
+        f.pad(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsePriorityError(String);
+
+impl fmt::Display for ParsePriorityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown priority '{}'; use low, normal, high or urgent", self.0)
+    }
+}
+
+impl Error for ParsePriorityError {}
+
+/// Accepts the names, their first letters, and todo.txt's `A` (urgent)
+/// to `D` (low).
+impl FromStr for Priority {
+    type Err = ParsePriorityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "low" | "l" | "d" => Ok(Priority::Low),
+            "normal" | "n" | "c" => Ok(Priority::Normal),
+            "high" | "h" | "b" => Ok(Priority::High),
+            "urgent" | "u" | "a" => Ok(Priority::Urgent),
+            _ => Err(ParsePriorityError(s.trim().to_string())),
+        }
+    }
+}
+
+/// A single to-do item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub id: TaskId,
+    pub description: String,
+    pub due_date: Option<DateTime<Utc>>,
+    /// When the task was added, or `None` for tasks from files that did
+    /// not record it.
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the task was marked done, or `None` while it is still open.
+    pub completed_at: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    /// Projects, contexts and tags, sorted. Those written in the
+    /// description are included; others can be added explicitly.
+    pub tags: Vec<Tag>,
+    /// How long before the due date each reminder fires; zero means at the
+    /// due time. Empty when the task should not remind at all.
+    pub lead_times: Vec<Duration>,
+    /// How the task repeats; completing it creates the next occurrence.
+    pub recurrence: Option<Recurrence>,
+    pub reminder: ReminderState,
+}
+
+impl Task {
+    pub fn new(id: TaskId, description: String, due_date: Option<DateTime<Utc>>) -> Self {
+        Task {
+            id,
+            tags: tags::parse_tags(&description),
+            description,
+            due_date,
+            created_at: Some(Utc::now()),
+            completed_at: None,
+            priority: Priority::default(),
+            lead_times: reminder::default_lead_times(),
+            recurrence: None,
+            reminder: ReminderState::default(),
+        }
+    }
+
+    /// Changes the description, replacing the tags the old one mentioned
+    /// with those in the new one. Tags added explicitly are kept.
+    pub fn set_description(&mut self, description: String) {
+        let old = tags::parse_tags(&self.description);
+        self.tags.retain(|tag| !old.contains(tag));
+        self.description = description;
+        self.add_tags(tags::parse_tags(&self.description));
+    }
+
+    pub fn add_tags<I: IntoIterator<Item = Tag>>(&mut self, tags: I) {
+        self.tags.extend(tags);
+        self.tags.sort();
+        self.tags.dedup();
+    }
+
+    /// Changes the due date, starting the reminder over for the new date.
+    pub fn set_due_date(&mut self, due_date: Option<DateTime<Utc>>) {
+        if due_date != self.due_date {
+            self.due_date = due_date;
+            self.reminder = ReminderState::default();
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.completed_at.is_some()
+    }
+
+    /// Marks the task done at `now`; completing it again keeps the original time.
+    pub fn complete(&mut self, now: DateTime<Utc>) {
+        self.completed_at.get_or_insert(now);
+    }
+
+    /// Marks the task open again; its reminder fires again if it is overdue.
+    pub fn reopen(&mut self) {
+        if self.completed_at.take().is_some() {
+            self.reminder = ReminderState::default();
+        }
+    }
+
+    /// Returns true once `now` has reached the due date of an open task.
+    pub fn is_due_at(&self, now: DateTime<Utc>) -> bool {
+        match self.due_date {
+            Some(due_date) => !self.is_completed() && now >= due_date,
+            None => false,
+        }
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.is_due_at(Utc::now())
+    }
+
+    /// Returns the open task that follows this one if it recurs: a copy due
+    /// at the next occurrence of its rule, reading times in `zone`.
+    /// Occurrences that are already over at `now` are skipped, so finishing
+    /// a chore late does not leave a backlog of overdue copies.
+    pub fn next_occurrence(&self, now: DateTime<Utc>, zone: Zone) -> Option<Task> {
+        let mut recurrence = self.recurrence.clone()?;
+        let mut due = self.due_date?;
+        loop {
+            if recurrence.count.is_some_and(|count| count <= 1) {
+                return None;
+            }
+            due = recurrence.next_after(due, zone)?;
+            if let Some(count) = recurrence.count.as_mut() {
+                *count -= 1;
+            }
+            if due > now {
+                break;
+            }
+        }
+        Some(Task {
+            id: TaskId::generate(),
+            due_date: Some(due),
+            created_at: Some(now),
+            completed_at: None,
+            recurrence: Some(recurrence),
+            reminder: ReminderState::default(),
+            ..self.clone()
+        })
+    }
+}
+
+/// Marks the task at `index` done at `now`. If that completes a recurring
+/// task, its next occurrence is appended and its position returned.
+pub fn complete(tasks: &mut Vec<Task>, index: usize, now: DateTime<Utc>) -> Option<usize> {
+    if tasks[index].is_completed() {
+        return None;
+    }
+    tasks[index].complete(now);
+    let next = tasks[index].next_occurrence(now, Zone::Local)?;
+    tasks.push(next);
+    Some(tasks.len() - 1)
+}
+
+/// Orders tasks for display: most urgent priority first, then earliest
+/// due date, with undated tasks last. Sorting with it is stable, so equal
+/// tasks keep the order they were added in.
+pub fn display_order(a: &Task, b: &Task) -> Ordering {
+    b.priority.cmp(&a.priority).then_with(|| match (a.due_date, b.due_date) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    })
+}
+
+/// Returns the position of the task with `id` in `tasks`.
+pub fn position(tasks: &[Task], id: TaskId) -> Option<usize> {
+    tasks.iter().position(|task| task.id == id)
+}
+
+/// Finds the task whose id is `query` or starts with it.
+///
+/// Matching ignores case and hyphens, so both the short form shown in
+/// listings and a full hyphenated UUID are accepted.
+pub fn find(tasks: &[Task], query: &str) -> Result<usize, LookupError> {
+    let prefix: String = query.chars().filter(|&c| c != '-').collect::<String>().to_ascii_lowercase();
+    if prefix.is_empty() {
+        return Err(LookupError::NotFound(query.to_string()));
+    }
+
+    let matches: Vec<usize> = tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, task)| task.id.to_string().starts_with(&prefix))
+        .map(|(i, _)| i)
+        .collect();
+    match matches.as_slice() {
+        [index] => Ok(*index),
+        [] => Err(LookupError::NotFound(query.to_string())),
+        _ => Err(LookupError::Ambiguous(query.to_string(), matches.iter().map(|&i| tasks[i].id).collect())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_with_id(id: &str) -> Task {
+        Task::new(id.parse().unwrap(), id.to_string(), None)
+    }
+
+    #[test]
+    fn find_accepts_unique_prefixes() {
+        let tasks = vec![
+            task_with_id("3f2a9c1e000000000000000000000000"),
+            task_with_id("3f2b0000000000000000000000000000"),
+        ];
+        assert_eq!(find(&tasks, "3f2a"), Ok(0));
+        assert_eq!(find(&tasks, "3F2B"), Ok(1));
+        assert_eq!(find(&tasks, "3f2a9c1e-0000-0000-0000-000000000000"), Ok(0));
+        assert!(matches!(find(&tasks, "3f2"), Err(LookupError::Ambiguous(..))));
+        assert!(matches!(find(&tasks, "ff"), Err(LookupError::NotFound(_))));
+        assert!(matches!(find(&tasks, ""), Err(LookupError::NotFound(_))));
+    }
+
+    #[test]
+    fn display_order_puts_urgent_and_early_tasks_first() {
+        let date = |day| Some(format!("2026-11-{:02}T09:00:00Z", day).parse().unwrap());
+        let mut tasks = [
+            Task::new(TaskId::generate(), "undated".to_string(), None),
+            Task::new(TaskId::generate(), "later".to_string(), date(3)),
+            Task::new(TaskId::generate(), "sooner".to_string(), date(2)),
+            Task { priority: Priority::Urgent, ..Task::new(TaskId::generate(), "urgent".to_string(), date(9)) },
+            Task { priority: Priority::Low, ..Task::new(TaskId::generate(), "low".to_string(), date(1)) },
+        ];
+        tasks.sort_by(display_order);
+        let order: Vec<&str> = tasks.iter().map(|task| task.description.as_str()).collect();
+        assert_eq!(order, ["urgent", "sooner", "later", "undated", "low"]);
+        assert_eq!("B".parse(), Ok(Priority::High));
+        assert!("important".parse::<Priority>().is_err());
+    }
+
+    #[test]
+    fn completing_a_recurring_task_schedules_the_next() {
+        let zone = Zone::parse("Europe/Berlin").unwrap();
+        let due: DateTime<Utc> = "2026-10-14T07:00:00Z".parse().unwrap();
+        let mut task = Task::new(TaskId::generate(), "Brushing Teeth".to_string(), Some(due));
+        task.recurrence = Some("FREQ=DAILY;COUNT=3".parse().unwrap());
+
+        let next = task.next_occurrence(due, zone).unwrap();
+        assert_ne!(next.id, task.id);
+        assert_eq!(next.due_date, Some("2026-10-15T07:00:00Z".parse().unwrap()));
+        assert_eq!(next.recurrence.as_ref().and_then(|r| r.count), Some(2));
+
+        // Finishing late skips missed occurrences, using up their count.
+        let late = "2026-10-16T12:00:00Z".parse().unwrap();
+        assert_eq!(task.next_occurrence(late, zone), None);
+        task.recurrence = Some("FREQ=DAILY".parse().unwrap());
+        assert_eq!(task.next_occurrence(late, zone).unwrap().due_date, Some("2026-10-17T07:00:00Z".parse().unwrap()));
+    }
+}
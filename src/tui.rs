@@ -0,0 +1,771 @@
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc;
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Duration, Local, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Cell, Clear, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::Frame;
+
+use crate::config::Config;
+use crate::dates::{self, DateSettings, DstChoice, ParsedDate, Resolution};
+use crate::reminder;
+use crate::scheduler::lock;
+use crate::sort::{self, ListOrder};
+use crate::store::{self, TaskStore};
+use crate::task::{self, Priority, Task, TaskId};
+use crate::view::{self, TableStyle, Theme};
+use crate::{Scheduler, SharedTasks};
+
+/// How long to wait for a key before redrawing, which keeps the
+/// countdowns current.
+const TICK: StdDuration = StdDuration::from_millis(500);
+/// How long the snooze key in a reminder pop-up puts a reminder off.
+const POPUP_SNOOZE_MINUTES: i64 = 10;
+
+/// What a line of input is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Purpose {
+    AddDescription,
+    AddDue,
+    EditDescription,
+    EditDue,
+    Snooze,
+}
+
+impl Purpose {
+    fn label(self) -> &'static str {
+        match self {
+            Purpose::AddDescription | Purpose::EditDescription => "Description",
+            Purpose::AddDue | Purpose::EditDue => "Due (blank for none)",
+            Purpose::Snooze => "Snooze for or until",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    Browse,
+    Input { purpose: Purpose, buffer: String },
+    /// The due date typed for `purpose` falls in a daylight saving change,
+    /// and which of its two instants is meant has to be picked.
+    ChooseDst { purpose: Purpose, parsed: ParsedDate },
+    ConfirmDelete(TaskId),
+}
+
+/// The state of the full-screen interface, separate from the terminal so
+/// it can be driven by key events and drawn to any backend.
+pub struct App {
+    tasks: SharedTasks,
+    order: ListOrder,
+    default_lead_times: Vec<Duration>,
+    selected: Option<TaskId>,
+    mode: Mode,
+    /// The task being added or edited while its fields are asked for.
+    draft: Option<Task>,
+    /// Reminders that fired and have not been dealt with, oldest first.
+    popups: VecDeque<TaskId>,
+    status: String,
+    color: bool,
+    /// How close a due date has to be to count as soon.
+    soon: Duration,
+    theme: Theme,
+    /// How dates are read and shown.
+    dates: DateSettings,
+    changed: bool,
+    quit: bool,
+}
+
+impl App {
+    /// Shows `tasks` in `order`; new tasks remind at `default_lead_times`.
+    pub fn new(tasks: SharedTasks, default_lead_times: Vec<Duration>, order: ListOrder) -> App {
+        let mut app = App {
+            tasks,
+            order,
+            default_lead_times,
+            selected: None,
+            mode: Mode::Browse,
+            draft: None,
+            popups: VecDeque::new(),
+            status: String::new(),
+            color: std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+            soon: Duration::days(1),
+            theme: Theme::default(),
+            dates: DateSettings::default(),
+            changed: false,
+            quit: false,
+        };
+        app.selected = app.rows().first().map(|task| task.id);
+        app
+    }
+
+    /// Colors and shows due dates as `style` does for tables.
+    pub fn with_style(mut self, style: &TableStyle) -> App {
+        self.color = style.color;
+        self.soon = style.soon;
+        self.theme = style.theme;
+        self.dates = style.dates.clone();
+        self
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Returns true once after the tasks were changed, so they can be saved.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Queues a pop-up for a reminder that just fired.
+    pub fn remind(&mut self, id: TaskId) {
+        self.forget_missing_popups();
+        if !self.popups.contains(&id) {
+            self.popups.push_back(id);
+        }
+    }
+
+    /// Drops the pop-ups of tasks that were deleted, here or elsewhere.
+    fn forget_missing_popups(&mut self) {
+        let tasks = lock(&self.tasks);
+        self.popups.retain(|&id| task::position(&tasks, id).is_some());
+    }
+
+    /// The tasks in the order they are listed.
+    fn rows(&self) -> Vec<Task> {
+        let mut tasks = lock(&self.tasks).clone();
+        sort::sort_tasks(&mut tasks, &self.order.keys);
+        tasks
+    }
+
+    fn selected_index(&self, rows: &[Task]) -> Option<usize> {
+        self.selected.and_then(|id| task::position(rows, id)).or(if rows.is_empty() { None } else { Some(0) })
+    }
+
+    fn selected_task(&self) -> Option<Task> {
+        let rows = self.rows();
+        self.selected_index(&rows).map(|i| rows[i].clone())
+    }
+
+    fn update<F: FnOnce(&mut Task)>(&mut self, id: TaskId, update: F) {
+        let mut tasks = lock(&self.tasks);
+        if let Some(index) = task::position(&tasks, id) {
+            update(&mut tasks[index]);
+            self.changed = true;
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent, now: DateTime<Utc>) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        // A pop-up takes the keys only while browsing, so that it does not
+        // act on keys typed into a prompt; it is shown once that is done.
+        self.forget_missing_popups();
+        if let (Mode::Browse, Some(&id)) = (&self.mode, self.popups.front()) {
+            self.handle_popup_key(id, key.code, now);
+            return;
+        }
+        match self.mode.clone() {
+            Mode::Browse => self.handle_browse_key(key.code, now),
+            Mode::Input { purpose, buffer } => self.handle_input_key(purpose, buffer, key.code, now),
+            Mode::ChooseDst { purpose, parsed } => {
+                let choice = match key.code {
+                    KeyCode::Char('1') => DstChoice::Earlier,
+                    KeyCode::Char('2') => DstChoice::Later,
+                    KeyCode::Esc => {
+                        self.mode = Mode::Browse;
+                        self.draft = None;
+                        self.status = "Cancelled.".to_string();
+                        return;
+                    }
+                    _ => return,
+                };
+                self.save_draft(purpose, Some(parsed.resolution.choose(choice)));
+                self.mode = Mode::Browse;
+            }
+            Mode::ConfirmDelete(id) => {
+                if key.code == KeyCode::Char('y') {
+                    let mut tasks = lock(&self.tasks);
+                    if let Some(index) = task::position(&tasks, id) {
+                        let removed = tasks.remove(index);
+                        self.status = format!("Deleted: {}", removed.description);
+                        self.changed = true;
+                    }
+                } else {
+                    self.status = "Kept the task.".to_string();
+                }
+                self.mode = Mode::Browse;
+            }
+        }
+    }
+
+    fn handle_popup_key(&mut self, id: TaskId, code: KeyCode, now: DateTime<Utc>) {
+        match code {
+            KeyCode::Enter | KeyCode::Char('a') => {
+                self.update(id, |task| task.reminder.acknowledge());
+                self.status = "Reminder acknowledged.".to_string();
+            }
+            KeyCode::Char('s') => {
+                let until = now + Duration::minutes(POPUP_SNOOZE_MINUTES);
+                self.update(id, |task| task.reminder.snooze(until));
+                self.status = format!("Snoozed until {}.", until.with_timezone(&Local).format("%H:%M"));
+            }
+            KeyCode::Char('c') => {
+                self.complete(id, now);
+            }
+            KeyCode::Esc => {}
+            _ => return,
+        }
+        self.popups.pop_front();
+    }
+
+    fn handle_browse_key(&mut self, code: KeyCode, now: DateTime<Utc>) {
+        let rows = self.rows();
+        let index = self.selected_index(&rows);
+        let selected = index.map(|i| rows[i].clone());
+        self.status.clear();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let Some(i) = index {
+                    self.selected = rows.get(i + 1).or(rows.get(i)).map(|task| task.id);
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let Some(i) = index {
+                    self.selected = Some(rows[i.saturating_sub(1)].id);
+                }
+            }
+            KeyCode::Home | KeyCode::Char('g') => self.selected = rows.first().map(|task| task.id),
+            KeyCode::End | KeyCode::Char('G') => self.selected = rows.last().map(|task| task.id),
+            KeyCode::Char('a') => {
+                let mut draft = Task::new(TaskId::generate(), String::new(), None);
+                draft.lead_times = self.default_lead_times.clone();
+                self.draft = Some(draft);
+                self.mode = Mode::Input { purpose: Purpose::AddDescription, buffer: String::new() };
+            }
+            KeyCode::Char('e') => {
+                if let Some(task) = selected {
+                    self.mode = Mode::Input { purpose: Purpose::EditDescription, buffer: task.description.clone() };
+                    self.draft = Some(task);
+                }
+            }
+            KeyCode::Char('c') | KeyCode::Char(' ') => {
+                if let Some(task) = selected {
+                    if task.is_completed() {
+                        self.update(task.id, Task::reopen);
+                        self.status = format!("Reopened: {}", task.description);
+                    } else {
+                        self.complete(task.id, now);
+                    }
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if let Some(task) = selected {
+                    self.mode = Mode::ConfirmDelete(task.id);
+                }
+            }
+            KeyCode::Char('s') if selected.is_some() => {
+                self.mode = Mode::Input { purpose: Purpose::Snooze, buffer: "10m".to_string() };
+            }
+            KeyCode::Char('p') => {
+                if let Some(task) = selected {
+                    let next = Priority::ALL[(Priority::ALL.iter().position(|&p| p == task.priority).unwrap_or(0) + 1)
+                        % Priority::ALL.len()];
+                    self.update(task.id, |task| task.priority = next);
+                    self.status = format!("Priority: {}", next);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn complete(&mut self, id: TaskId, now: DateTime<Utc>) {
+        let mut tasks = lock(&self.tasks);
+        if let Some(index) = task::position(&tasks, id) {
+            let next = task::complete(&mut tasks, index, now);
+            self.status = format!("Completed: {}", tasks[index].description);
+            if let Some(due) = next.and_then(|i| tasks[i].due_date) {
+                self.status.push_str(&format!("; next due {}", format_due(due, &self.dates)));
+            }
+            self.changed = true;
+        }
+    }
+
+    /// Adds the task being drafted, or saves the edit of it, due at `due`.
+    fn save_draft(&mut self, purpose: Purpose, due: Option<DateTime<Utc>>) {
+        let draft = match self.draft.take() {
+            Some(draft) => draft,
+            None => return,
+        };
+        let mut tasks = lock(&self.tasks);
+        if purpose == Purpose::AddDue {
+            let mut task = draft;
+            task.set_due_date(due);
+            self.status = format!("Added: {}", task.description);
+            self.selected = Some(task.id);
+            tasks.push(task);
+            self.changed = true;
+            return;
+        }
+        // Only the edited fields are copied, as the task may have changed
+        // while they were typed, e.g. when its reminder fired.
+        let task = match task::position(&tasks, draft.id) {
+            Some(index) => &mut tasks[index],
+            None => {
+                self.status = "The task was deleted meanwhile.".to_string();
+                return;
+            }
+        };
+        task.set_description(draft.description);
+        task.set_due_date(due);
+        if due.is_none() {
+            task.recurrence = None;
+        }
+        self.status = format!("Updated: {}", task.description);
+        self.changed = true;
+    }
+
+    fn handle_input_key(&mut self, purpose: Purpose, mut buffer: String, code: KeyCode, now: DateTime<Utc>) {
+        match code {
+            KeyCode::Esc => {
+                self.mode = Mode::Browse;
+                self.draft = None;
+                self.status = "Cancelled.".to_string();
+            }
+            KeyCode::Backspace => {
+                buffer.pop();
+                self.mode = Mode::Input { purpose, buffer };
+            }
+            KeyCode::Char(c) => {
+                buffer.push(c);
+                self.mode = Mode::Input { purpose, buffer };
+            }
+            KeyCode::Enter => match self.submit(purpose, buffer.trim(), now) {
+                Ok(next) => self.mode = next,
+                Err(message) => self.status = message,
+            },
+            _ => {}
+        }
+    }
+
+    /// Applies a line of input, returning the next mode or why the input
+    /// was not accepted.
+    fn submit(&mut self, purpose: Purpose, input: &str, now: DateTime<Utc>) -> Result<Mode, String> {
+        match purpose {
+            Purpose::AddDescription | Purpose::EditDescription => {
+                if input.is_empty() {
+                    return Err("The description cannot be empty.".to_string());
+                }
+                let draft = self.draft.as_mut().ok_or_else(String::new)?;
+                draft.set_description(input.to_string());
+                let next = if purpose == Purpose::AddDescription { Purpose::AddDue } else { Purpose::EditDue };
+                let buffer = draft.due_date.map(|due| format_due(due, &self.dates)).unwrap_or_default();
+                Ok(Mode::Input { purpose: next, buffer })
+            }
+            Purpose::AddDue | Purpose::EditDue => {
+                let due = match input {
+                    "" | "none" => None,
+                    input => {
+                        let parsed = dates::parse_due_date_at(input, now, dates::Zone::Local, &self.dates)
+                            .map_err(|e| e.to_string())?;
+                        match parsed.resolution.unique() {
+                            Some(instant) => Some(instant),
+                            None => return Ok(Mode::ChooseDst { purpose, parsed }),
+                        }
+                    }
+                };
+                self.save_draft(purpose, due);
+                Ok(Mode::Browse)
+            }
+            Purpose::Snooze => {
+                let until = dates::parse_snooze_at(input, now, dates::Zone::Local, &self.dates).map_err(|e| e.to_string())?;
+                if let Some(task) = self.selected_task() {
+                    self.update(task.id, |task| task.reminder.snooze(until));
+                    self.status = format!("Snoozed '{}' until {}", task.description, format_due(until, &self.dates));
+                }
+                Ok(Mode::Browse)
+            }
+        }
+    }
+
+    /// Draws the task list, the details of the selected task, the status
+    /// line and any reminder pop-up, with countdowns relative to `now`.
+    pub fn draw(&self, frame: &mut Frame, now: DateTime<Utc>) {
+        let rows = self.rows();
+        let selected = self.selected_index(&rows);
+        let [main, status] = Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        let [list, detail] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main);
+
+        let table_rows: Vec<Row> = rows
+            .iter()
+            .map(|task| {
+                let marker = if task.is_completed() { "[x]" } else { "[ ]" };
+                let priority = if task.priority == Priority::Normal { String::new() } else { task.priority.to_string() };
+                Row::new(vec![
+                    Cell::from(marker),
+                    Cell::from(priority),
+                    Cell::from(view::single_line(&task.description)),
+                    Cell::from(view::relative_time(task, now)),
+                ])
+                .style(self.due_style(task, now))
+            })
+            .collect();
+        let table = Table::new(
+            table_rows,
+            [Constraint::Length(3), Constraint::Length(6), Constraint::Fill(1), Constraint::Length(16)],
+        )
+        .header(Row::new(["", "Pri", "Description", "When"]).style(Style::new().add_modifier(Modifier::BOLD)))
+        .block(Block::bordered().title(format!(" Tasks ({}) ", rows.len())))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = TableState::default().with_selected(selected);
+        frame.render_stateful_widget(table, list, &mut state);
+
+        let details = match selected.map(|i| &rows[i]) {
+            Some(task) => detail_text(task, now, &self.dates),
+            None => Text::from("No tasks. Press 'a' to add one."),
+        };
+        frame.render_widget(
+            Paragraph::new(details).wrap(Wrap { trim: false }).block(Block::bordered().title(" Details ")),
+            detail,
+        );
+
+        let line = match &self.mode {
+            Mode::Input { purpose, buffer } => format!("{}: {}_  {}", purpose.label(), buffer, self.status),
+            Mode::ChooseDst { parsed, .. } => {
+                let problem = match parsed.resolution {
+                    Resolution::Ambiguous { .. } => "Happens twice",
+                    _ => "Does not exist",
+                };
+                format!(
+                    "{} in {}: 1 {}  2 {}  Esc cancel",
+                    problem,
+                    parsed.zone,
+                    parsed.zone.format(parsed.resolution.choose(DstChoice::Earlier)),
+                    parsed.zone.format(parsed.resolution.choose(DstChoice::Later))
+                )
+            }
+            Mode::ConfirmDelete(_) => "Delete this task? (y/n)".to_string(),
+            Mode::Browse if !self.status.is_empty() => self.status.clone(),
+            Mode::Browse => {
+                "a add  e edit  c complete  d delete  s snooze  p priority  j/k move  q quit".to_string()
+            }
+        };
+        frame.render_widget(Paragraph::new(line), status);
+
+        let popups: Vec<&Task> = self.popups.iter().filter_map(|&id| rows.iter().find(|task| task.id == id)).collect();
+        if let (Mode::Browse, Some(task)) = (&self.mode, popups.first()) {
+            let mut message = Vec::new();
+            let _ = reminder::write_reminder(&mut message, task, now);
+            let mut text = Text::from(String::from_utf8_lossy(&message).trim().to_string());
+            text.push_line(Line::default());
+            text.push_line(format!(
+                "Enter acknowledge  s snooze {}m  c complete  Esc dismiss",
+                POPUP_SNOOZE_MINUTES
+            ));
+            let area = centered(frame.area(), 60, 7);
+            frame.render_widget(Clear, area);
+            let title = if popups.len() > 1 { format!(" Reminder (1 of {}) ", popups.len()) } else { " Reminder ".to_string() };
+            frame.render_widget(
+                Paragraph::new(text).wrap(Wrap { trim: false }).block(Block::bordered().title(title).style(self.popup_style())),
+                area,
+            );
+        }
+    }
+
+    fn due_style(&self, task: &Task, now: DateTime<Utc>) -> Style {
+        match task.due_date {
+            _ if !self.color || task.is_completed() => Style::new(),
+            Some(due) if due <= now => Style::new().fg(terminal_color(self.theme.overdue)),
+            Some(due) if due - now <= self.soon => Style::new().fg(terminal_color(self.theme.soon)),
+            _ => Style::new(),
+        }
+    }
+
+    fn popup_style(&self) -> Style {
+        if self.color {
+            Style::new().fg(Color::Yellow)
+        } else {
+            Style::new()
+        }
+    }
+}
+
+fn detail_text(task: &Task, now: DateTime<Utc>, settings: &DateSettings) -> Text<'static> {
+    let mut lines = vec![Line::from(task.description.clone()), Line::default()];
+    lines.push(Line::from(format!("ID:        {}", task.id.short())));
+    lines.push(Line::from(format!("Priority:  {}", task.priority)));
+    if !task.tags.is_empty() {
+        lines.push(Line::from(format!("Tags:      {}", view::join_tags(task))));
+    }
+    match task.due_date {
+        Some(due) => {
+            lines.push(Line::from(format!("Due:       {} ({})", format_due(due, settings), view::relative_time(task, now))));
+            if !task.is_completed() {
+                lines.push(Line::from(format!("Reminders: {}", view::describe_lead_times(task))));
+            }
+        }
+        None => lines.push(Line::from("Due:       none")),
+    }
+    if let Some(recurrence) = &task.recurrence {
+        lines.push(Line::from(format!("Repeats:   {}", recurrence.describe())));
+    }
+    if let Some(completed_at) = task.completed_at {
+        lines.push(Line::from(format!("Completed: {}", format_due(completed_at, settings))));
+    } else if let Some(until) = task.reminder.snoozed_until {
+        lines.push(Line::from(format!("Snoozed until {}", format_due(until, settings))));
+    } else if task.reminder.pending {
+        lines.push(Line::from("Reminder pending"));
+    }
+    if let Some(created_at) = task.created_at {
+        lines.push(Line::from(format!("Created:   {}", format_due(created_at, settings))));
+    }
+    Text::from(lines)
+}
+
+fn format_due(date: DateTime<Utc>, settings: &DateSettings) -> String {
+    dates::format_local(date, settings)
+}
+
+fn terminal_color(color: view::Color) -> Color {
+    match color {
+        view::Color::Black => Color::Black,
+        view::Color::Red => Color::Red,
+        view::Color::Green => Color::Green,
+        view::Color::Yellow => Color::Yellow,
+        view::Color::Blue => Color::Blue,
+        view::Color::Magenta => Color::Magenta,
+        view::Color::Cyan => Color::Cyan,
+        view::Color::White => Color::White,
+    }
+}
+
+/// A `width` by `height` rectangle in the middle of `area`, shrunk to fit.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+/// Runs the full-screen interface on the terminal until the user quits,
+/// saving to `store` after every change. New tasks, list order and colors
+/// follow `config`.
+pub fn run<S: TaskStore>(store: &mut S, config: &Config) -> io::Result<()> {
+    let mut saved = store.load()?;
+    let tasks: SharedTasks = std::sync::Arc::new(std::sync::Mutex::new(saved.clone()));
+    let (fired, reminders) = mpsc::channel();
+    let hours = config.reminder_hours();
+    let filter = move |_: &Task| hours.as_ref().is_none_or(|hours| hours.contains(Utc::now()));
+    let scheduler = Scheduler::spawn_filtered(tasks.clone(), filter, move |task| {
+        let _ = fired.send(task.id);
+    });
+    let mut app = App::new(tasks.clone(), config.lead_times.clone(), config.order.clone()).with_style(&config.table_style());
+
+    let mut terminal = ratatui::init();
+    let result = (|| -> io::Result<()> {
+        while !app.should_quit() {
+            let mut fired_any = false;
+            for id in reminders.try_iter() {
+                app.remind(id);
+                fired_any = true;
+            }
+            terminal.draw(|frame| app.draw(frame, Utc::now()))?;
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    app.handle_key(key, Utc::now());
+                }
+            }
+            // Firing marks reminders as sent, which is saved too. Changes
+            // other instances saved are picked up on every tick.
+            let changed = app.take_changed() || fired_any;
+            if store::sync(store, &tasks, &mut saved)? || changed {
+                scheduler.wake();
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    scheduler.shutdown();
+    result?;
+    store::sync(store, &tasks, &mut saved).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use std::sync::{Arc, Mutex};
+
+    fn screen(app: &App, now: DateTime<Utc>) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| app.draw(frame, now)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let mut text = String::new();
+        for y in 0..buffer.area.height {
+            for x in 0..buffer.area.width {
+                text.push_str(buffer[(x, y)].symbol());
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    fn press(app: &mut App, keys: &str, now: DateTime<Utc>) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                c => KeyCode::Char(c),
+            };
+            app.handle_key(KeyEvent::from(code), now);
+        }
+    }
+
+    fn app_with(tasks: Vec<Task>) -> (App, SharedTasks) {
+        let shared: SharedTasks = Arc::new(Mutex::new(tasks));
+        let mut app = App::new(shared.clone(), vec![Duration::zero()], ListOrder::default());
+        app.color = false;
+        (app, shared)
+    }
+
+    #[test]
+    fn lists_tasks_with_countdowns_and_details() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let (mut app, _) = app_with(vec![
+            Task::new(TaskId::generate(), "Water plants @home".to_string(), Some(now + Duration::hours(3))),
+            Task::new(TaskId::generate(), "Pay rent".to_string(), Some(now - Duration::days(2))),
+        ]);
+        let text = screen(&app, now);
+        assert!(text.contains("Tasks (2)"));
+        assert!(text.contains("in 3h") && text.contains("2 days overdue"));
+        // The earliest due task is listed and selected first.
+        assert!(text.find("Pay rent").unwrap() < text.find("Water plants").unwrap());
+        assert!(text.contains("Due:       ") && text.contains("Reminders: at due time"));
+
+        press(&mut app, "j", now);
+        assert!(screen(&app, now).contains("Tags:      @home"));
+    }
+
+    #[test]
+    fn keys_add_complete_and_delete_tasks() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let (mut app, tasks) = app_with(Vec::new());
+
+        press(&mut app, "aBuy milk +shop\nin 2 hours\n", now);
+        assert!(app.take_changed());
+        {
+            let tasks = lock(&tasks);
+            assert_eq!(tasks.len(), 1);
+            assert_eq!(tasks[0].due_date, Some(now + Duration::hours(2)));
+            assert_eq!(tasks[0].tags, vec!["+shop".parse().unwrap()]);
+        }
+
+        press(&mut app, "c", now);
+        assert!(lock(&tasks)[0].is_completed());
+        press(&mut app, "e\x1b", now);
+        assert!(screen(&app, now).contains("Cancelled."));
+
+        press(&mut app, "dn", now);
+        assert_eq!(lock(&tasks).len(), 1);
+        press(&mut app, "dy", now);
+        assert!(lock(&tasks).is_empty());
+        assert!(screen(&app, now).contains("No tasks."));
+    }
+
+    #[test]
+    fn asks_which_time_is_meant_in_a_dst_change() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let (mut app, tasks) = app_with(Vec::new());
+
+        press(&mut app, "aBackup\n2026-10-25 02:30 Europe/Berlin\n", now);
+        assert!(lock(&tasks).is_empty());
+        let text = screen(&app, now);
+        assert!(text.contains("Happens twice in Europe/Berlin"), "{}", text);
+        assert!(text.contains("1 2026-10-25 02:30:00 CEST  2 2026-10-25 02:30:00 CET"), "{}", text);
+        // Other keys wait for the choice.
+        press(&mut app, "x", now);
+        assert!(lock(&tasks).is_empty());
+
+        press(&mut app, "2", now);
+        assert_eq!(lock(&tasks)[0].due_date, Some("2026-10-25T01:30:00Z".parse().unwrap()));
+        assert_eq!(app.mode, Mode::Browse);
+    }
+
+    #[test]
+    fn edits_keep_changes_made_meanwhile() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let mut task = Task::new(TaskId::generate(), "Call Alex".to_string(), Some(now));
+        task.priority = Priority::High;
+        let (mut app, tasks) = app_with(vec![task]);
+
+        press(&mut app, "e", now);
+        reminder::fire_due(&mut lock(&tasks), now);
+        lock(&tasks)[0].priority = Priority::Urgent;
+        press(&mut app, " back\n\n", now);
+        {
+            let tasks = lock(&tasks);
+            assert_eq!(tasks[0].description, "Call Alex back");
+            assert_eq!(tasks[0].priority, Priority::Urgent);
+            assert!(tasks[0].reminder.pending);
+        }
+
+        press(&mut app, "e", now);
+        lock(&tasks).clear();
+        press(&mut app, "\n\n", now);
+        assert!(lock(&tasks).is_empty());
+        assert!(screen(&app, now).contains("The task was deleted meanwhile."));
+    }
+
+    #[test]
+    fn reminders_pop_up_and_can_be_snoozed() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let task = Task::new(TaskId::generate(), "Call Alex".to_string(), Some(now));
+        let id = task.id;
+        let (mut app, tasks) = app_with(vec![task]);
+        reminder::fire_due(&mut lock(&tasks), now);
+        app.remind(id);
+
+        let text = screen(&app, now);
+        assert!(text.contains("Reminder") && text.contains("Task 'Call Alex' is due!"));
+        // Other keys do nothing while the pop-up is open.
+        press(&mut app, "d", now);
+        assert_eq!(app.mode, Mode::Browse);
+
+        press(&mut app, "s", now);
+        assert_eq!(lock(&tasks)[0].reminder.snoozed_until, Some(now + Duration::minutes(10)));
+        assert!(!screen(&app, now).contains("is due!"));
+    }
+
+    #[test]
+    fn pop_ups_wait_for_prompts_and_go_with_their_task() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let task = Task::new(TaskId::generate(), "Call Alex".to_string(), Some(now));
+        let id = task.id;
+        let (mut app, tasks) = app_with(vec![task]);
+
+        // A reminder firing mid-prompt leaves the typing alone.
+        press(&mut app, "aSnacks", now);
+        reminder::fire_due(&mut lock(&tasks), now);
+        app.remind(id);
+        assert!(!screen(&app, now).contains("is due!"));
+        press(&mut app, "\n\n", now);
+        assert_eq!(lock(&tasks)[1].description, "Snacks");
+        assert!(lock(&tasks)[0].reminder.pending);
+        assert!(screen(&app, now).contains("is due!"));
+
+        // Once its task is deleted elsewhere, the pop-up no longer takes keys.
+        lock(&tasks).retain(|task| task.id != id);
+        press(&mut app, "d", now);
+        assert!(matches!(app.mode, Mode::ConfirmDelete(_)));
+        assert!(app.popups.is_empty());
+    }
+}
@@ -0,0 +1,581 @@
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::dates::{self, DateSettings, Zone};
+use crate::sort::{self, Group, ListOrder};
+use crate::storage;
+use crate::tags::Tag;
+use crate::task::{Priority, Task};
+
+/// Writes the human-readable task list shown by the interactive menu.
+/// Tasks whose priority is not normal are flagged, e.g. `[URGENT]`.
+pub fn write_task_list<W: Write>(mut writer: W, tasks: &[Task], settings: &DateSettings) -> io::Result<()> {
+    for task in tasks {
+        write_task(&mut writer, task, settings)?;
+    }
+    Ok(())
+}
+
+/// Writes each group under a header with its number of tasks.
+pub fn write_groups<W: Write>(mut writer: W, groups: &[Group<'_>], settings: &DateSettings) -> io::Result<()> {
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            writeln!(writer)?;
+        }
+        writeln!(writer, "== {} ({}) ==", group.title, group.tasks.len())?;
+        for task in &group.tasks {
+            write_task(&mut writer, task, settings)?;
+        }
+    }
+    Ok(())
+}
+
+/// Sorts `tasks` as `order` says and writes them as a table, grouped if
+/// it asks for groups.
+pub fn write_ordered_table<W: Write>(
+    writer: W,
+    tasks: &mut [Task],
+    order: &ListOrder,
+    style: &TableStyle,
+) -> io::Result<()> {
+    sort::sort_tasks(tasks, &order.keys);
+    let now = Utc::now();
+    let groups = match order.grouping {
+        Some(grouping) => sort::group_tasks(tasks, grouping, now, Zone::Local, style.dates.week_start),
+        None => vec![Group { title: String::new(), tasks: tasks.iter().collect() }],
+    };
+    write_table(writer, &groups, order.grouping.is_some(), now, style)
+}
+
+/// A terminal color for highlighting tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    pub const ALL: [Color; 8] =
+        [Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan, Color::White];
+
+    /// Parses a color name such as `red`.
+    pub fn parse(name: &str) -> Option<Color> {
+        let name = name.trim().to_lowercase();
+        Color::ALL.iter().copied().find(|color| color.to_string() == name)
+    }
+
+    /// The ANSI escape sequence selecting this color as the foreground.
+    fn escape(self) -> &'static str {
+        match self {
+            Color::Black => "\x1b[30m",
+            Color::Red => "\x1b[31m",
+            Color::Green => "\x1b[32m",
+            Color::Yellow => "\x1b[33m",
+            Color::Blue => "\x1b[34m",
+            Color::Magenta => "\x1b[35m",
+            Color::Cyan => "\x1b[36m",
+            Color::White => "\x1b[37m",
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Color::Black => "black",
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Blue => "blue",
+            Color::Magenta => "magenta",
+            Color::Cyan => "cyan",
+            Color::White => "white",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The colors overdue tasks and those due soon are shown in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub overdue: Color,
+    pub soon: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme { overdue: Color::Red, soon: Color::Yellow }
+    }
+}
+
+/// How task tables are drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableStyle {
+    /// Whether to color overdue tasks and those due soon.
+    pub color: bool,
+    /// The width to fit rows into by shortening descriptions, if any.
+    pub width: Option<usize>,
+    /// How close a due date has to be to count as soon.
+    pub soon: Duration,
+    pub theme: Theme,
+    /// How due dates are shown, and where weeks start when grouping.
+    pub dates: DateSettings,
+}
+
+impl TableStyle {
+    /// No color and no width limit, for files and pipes.
+    pub fn plain() -> TableStyle {
+        TableStyle {
+            color: false,
+            width: None,
+            soon: Duration::days(1),
+            theme: Theme::default(),
+            dates: DateSettings::default(),
+        }
+    }
+
+    /// Fits tables to the terminal when stdout is one, coloring them unless
+    /// `NO_COLOR` is set to a non-empty value.
+    pub fn for_stdout() -> TableStyle {
+        if !io::stdout().is_terminal() {
+            return TableStyle::plain();
+        }
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let width = terminal_size::terminal_size().map(|(width, _)| usize::from(width.0));
+        TableStyle { color: !no_color, width, ..TableStyle::plain() }
+    }
+}
+
+/// Writes `tasks` as an aligned table of ID, priority, description, due
+/// date, time left and tags, as of `now`.
+pub fn write_task_table<W: Write>(writer: W, tasks: &[Task], now: DateTime<Utc>, style: &TableStyle) -> io::Result<()> {
+    let group = Group { title: String::new(), tasks: tasks.iter().collect() };
+    write_table(writer, &[group], false, now, style)
+}
+
+const HEADINGS: [&str; 6] = ["ID", "Pri", "Description", "Due", "When", "Tags"];
+const DESCRIPTION: usize = 2;
+/// Descriptions are not shortened below this many columns, even if the
+/// row then overflows the terminal.
+const MIN_DESCRIPTION_WIDTH: usize = 12;
+
+/// Writes every group's tasks in one table so the columns line up across
+/// groups, with a header line per group if `headers` is set.
+fn write_table<W: Write>(
+    mut writer: W,
+    groups: &[Group<'_>],
+    headers: bool,
+    now: DateTime<Utc>,
+    style: &TableStyle,
+) -> io::Result<()> {
+    let rows: Vec<Vec<(String, [String; 6])>> = groups
+        .iter()
+        .map(|group| group.tasks.iter().map(|task| (row_color(task, now, style), table_row(task, now, &style.dates))).collect())
+        .collect();
+    let mut widths = HEADINGS.map(UnicodeWidthStr::width);
+    for (_, row) in rows.iter().flatten() {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+    if let Some(limit) = style.width {
+        let others: usize = widths.iter().enumerate().filter(|&(i, _)| i != DESCRIPTION).map(|(_, w)| w + 2).sum();
+        let available = limit.saturating_sub(others).max(MIN_DESCRIPTION_WIDTH);
+        widths[DESCRIPTION] = widths[DESCRIPTION].min(available);
+    }
+
+    let bold = if style.color { BOLD } else { "" };
+    write_row(&mut writer, &HEADINGS.map(str::to_string), &widths, bold, style.color)?;
+    for (group, rows) in groups.iter().zip(&rows) {
+        if headers {
+            writeln!(writer)?;
+            let title = format!("{} ({})", group.title, group.tasks.len());
+            if style.color {
+                writeln!(writer, "{}{}{}", BOLD, title, RESET)?;
+            } else {
+                writeln!(writer, "{}", title)?;
+            }
+        }
+        for (color, row) in rows {
+            write_row(&mut writer, row, &widths, color, style.color)?;
+        }
+    }
+    Ok(())
+}
+
+fn table_row(task: &Task, now: DateTime<Utc>, settings: &DateSettings) -> [String; 6] {
+    let priority = if task.priority == Priority::Normal { String::new() } else { task.priority.to_string() };
+    let description = single_line(&task.description);
+    let description = if task.is_completed() { format!("[x] {}", description) } else { description };
+    let due = task.due_date.map(|due| dates::format_local(due, settings));
+    [
+        task.id.short(),
+        priority,
+        description,
+        due.unwrap_or_default(),
+        relative_time(task, now),
+        single_line(&join_tags(task)),
+    ]
+}
+
+/// Replaces line breaks, escape sequences and other control characters
+/// with spaces, so that text cannot break a row or restyle the terminal.
+pub(crate) fn single_line(text: &str) -> String {
+    text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect()
+}
+
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+fn row_color(task: &Task, now: DateTime<Utc>, style: &TableStyle) -> String {
+    let color = match task.due_date {
+        _ if !style.color || task.is_completed() => "",
+        Some(due) if due <= now => style.theme.overdue.escape(),
+        Some(due) if due - now <= style.soon => style.theme.soon.escape(),
+        _ => "",
+    };
+    color.to_string()
+}
+
+fn write_row<W: Write>(writer: &mut W, cells: &[String; 6], widths: &[usize; 6], color: &str, colored: bool) -> io::Result<()> {
+    let mut line = String::new();
+    for (i, (cell, &width)) in cells.iter().zip(widths).enumerate() {
+        if i > 0 {
+            line.push_str("  ");
+        }
+        let cell = truncate(cell, width);
+        line.push_str(&cell);
+        if i + 1 < cells.len() {
+            line.push_str(&" ".repeat(width - cell.width()));
+        }
+    }
+    let line = line.trim_end();
+    if colored && !color.is_empty() {
+        writeln!(writer, "{}{}{}", color, line, RESET)
+    } else {
+        writeln!(writer, "{}", line)
+    }
+}
+
+/// Shortens `text` to at most `width` columns, ending it with an ellipsis
+/// if anything was cut.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    out.push('…');
+    out
+}
+
+/// Describes how far off a due date is, e.g. "in 3h" or "2 days overdue".
+pub(crate) fn relative_time(task: &Task, now: DateTime<Utc>) -> String {
+    let due = match task.due_date {
+        Some(due) => due,
+        None => return String::new(),
+    };
+    if task.is_completed() {
+        return "done".to_string();
+    }
+    let distance = if due >= now { due - now } else { now - due };
+    let amount = if distance < Duration::minutes(1) {
+        return "now".to_string();
+    } else if distance < Duration::hours(1) {
+        format!("{}m", distance.num_minutes())
+    } else if distance < Duration::days(1) {
+        format!("{}h", distance.num_hours())
+    } else if distance < Duration::weeks(2) {
+        plural(distance.num_days(), "day")
+    } else if distance < Duration::days(60) {
+        plural(distance.num_weeks(), "week")
+    } else if distance < Duration::days(365) {
+        plural(distance.num_days() / 30, "month")
+    } else {
+        plural(distance.num_days() / 365, "year")
+    };
+    if due >= now {
+        format!("in {}", amount)
+    } else {
+        format!("{} overdue", amount)
+    }
+}
+
+fn plural(count: i64, unit: &str) -> String {
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+fn write_task<W: Write>(writer: &mut W, task: &Task, settings: &DateSettings) -> io::Result<()> {
+    let marker = if task.is_completed() { "[x]" } else { "[ ]" };
+    write!(writer, "Task {}: {} ", task.id.short(), marker)?;
+    if task.priority != Priority::Normal {
+        write!(writer, "[{}] ", task.priority.to_string().to_uppercase())?;
+    }
+    writeln!(writer, "{}", single_line(&task.description))?;
+    if !task.tags.is_empty() {
+        writeln!(writer, "Tags: {}", join_tags(task))?;
+    }
+    if let Some(due_date) = task.due_date {
+        writeln!(writer, "Due date: {}", dates::format_local(due_date, settings))?;
+        if !task.is_completed() {
+            writeln!(writer, "Reminders: {}", describe_lead_times(task))?;
+        }
+        if let Some(recurrence) = &task.recurrence {
+            writeln!(writer, "Repeats: {}", recurrence.describe())?;
+        }
+    } else {
+        writeln!(writer, "No due date")?;
+    }
+    if let Some(completed_at) = task.completed_at {
+        writeln!(writer, "Completed: {}", dates::format_local(completed_at, settings))?;
+    } else if let Some(snoozed_until) = task.reminder.snoozed_until {
+        writeln!(writer, "Reminder snoozed until {}", dates::format_local(snoozed_until, settings))?;
+    } else if task.reminder.pending {
+        writeln!(writer, "Reminder pending")?;
+    }
+    Ok(())
+}
+
+/// Writes each tag with the number of open tasks carrying it, as returned
+/// by [`tags::open_counts`](crate::tags::open_counts).
+pub fn write_tag_counts<W: Write>(mut writer: W, counts: &[(Tag, usize)]) -> io::Result<()> {
+    let width = counts.iter().map(|(tag, _)| tag.to_string().chars().count()).max().unwrap_or(0);
+    for (tag, count) in counts {
+        writeln!(writer, "{:width$}  {} open", tag.to_string(), count, width = width)?;
+    }
+    Ok(())
+}
+
+/// Writes the reminders still to fire, as returned by
+/// [`reminder::upcoming`](crate::reminder::upcoming), one per line.
+pub fn write_upcoming<W: Write>(
+    mut writer: W,
+    upcoming: &[(DateTime<Utc>, &Task)],
+    settings: &DateSettings,
+) -> io::Result<()> {
+    for &(at, task) in upcoming {
+        let reason = if Some(at) == task.reminder.snoozed_until {
+            "snoozed".to_string()
+        } else {
+            match task.due_date.map(|due| due - at) {
+                Some(lead_time) if lead_time > Duration::zero() => {
+                    format!("{} before due", dates::format_lead_time(lead_time))
+                }
+                _ => "at due time".to_string(),
+            }
+        };
+        writeln!(
+            writer,
+            "{}  Task {}: {} ({})",
+            dates::format_local(at, settings),
+            task.id.short(),
+            single_line(&task.description),
+            reason
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes `tasks` as a spreadsheet-friendly CSV export. Descriptions are
+/// kept exactly, in quotes if they hold line breaks or other control
+/// characters.
+pub fn write_csv_export<W: Write>(mut writer: W, tasks: &[Task]) -> io::Result<()> {
+    storage::write_record(&mut writer, &["ID", "Description", "Priority", "Tags", "Due Date", "Completed"])?;
+    for task in tasks {
+        let due_date = task.due_date.map(|d| d.to_string()).unwrap_or_default();
+        let completed_at = task.completed_at.map(|d| d.to_string()).unwrap_or_default();
+        storage::write_record(
+            &mut writer,
+            &[
+                task.id.to_string(),
+                task.description.clone(),
+                task.priority.to_string(),
+                join_tags(task),
+                due_date,
+                completed_at,
+            ],
+        )?;
+    }
+    writer.flush()
+}
+
+/// Writes `tasks` as a JSON array for scripts.
+///
+/// Each entry carries the task id used by the command line, the
+/// description, the priority, the tags, the due, creation and completion
+/// dates in RFC 3339 (or `null`), the
+/// reminder lead times, the recurrence as an RFC 5545 `RRULE` (or `null`),
+/// and whether a reminder is waiting to be acknowledged.
+pub fn write_json<W: Write>(mut writer: W, tasks: &[Task]) -> io::Result<()> {
+    writeln!(writer, "[")?;
+    for (i, task) in tasks.iter().enumerate() {
+        let separator = if i + 1 < tasks.len() { "," } else { "" };
+        let tags: Vec<String> = task.tags.iter().map(|tag| json_string(&tag.to_string())).collect();
+        let lead_times: Vec<String> =
+            task.lead_times.iter().map(|&lead_time| json_string(&dates::format_lead_time(lead_time))).collect();
+        writeln!(
+            writer,
+            "  {{\"id\": \"{}\", \"description\": {}, \"priority\": \"{}\", \"tags\": [{}], \"due_date\": {}, \"created_at\": {}, \"completed_at\": {}, \"lead_times\": [{}], \"recurrence\": {}, \"reminder_pending\": {}}}{}",
+            task.id,
+            json_string(&task.description),
+            task.priority,
+            tags.join(", "),
+            json_date(task.due_date),
+            json_date(task.created_at),
+            json_date(task.completed_at),
+            lead_times.join(", "),
+            task.recurrence.as_ref().map_or("null".to_string(), |r| json_string(&r.to_string())),
+            task.reminder.pending,
+            separator
+        )?;
+    }
+    writeln!(writer, "]")?;
+    writer.flush()
+}
+
+pub(crate) fn join_tags(task: &Task) -> String {
+    let tags: Vec<String> = task.tags.iter().map(Tag::to_string).collect();
+    tags.join(" ")
+}
+
+/// Describes when a task reminds, e.g. "1d before, 30m before, at due time".
+pub fn describe_lead_times(task: &Task) -> String {
+    if task.lead_times.is_empty() {
+        return "none".to_string();
+    }
+    let parts: Vec<String> = task
+        .lead_times
+        .iter()
+        .map(|&lead_time| {
+            if lead_time > Duration::zero() {
+                format!("{} before", dates::format_lead_time(lead_time))
+            } else {
+                "at due time".to_string()
+            }
+        })
+        .collect();
+    parts.join(", ")
+}
+
+pub(crate) fn json_date(date: Option<DateTime<Utc>>) -> String {
+    match date {
+        Some(date) => json_string(&date.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        None => "null".to_string(),
+    }
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::TaskId;
+
+    fn task(description: &str, due: Option<DateTime<Utc>>) -> Task {
+        Task::new(TaskId::generate(), description.to_string(), due)
+    }
+
+    #[test]
+    fn describes_time_left() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let relative = |offset: Duration| relative_time(&task("x", Some(now + offset)), now);
+        assert_eq!(relative(Duration::hours(3)), "in 3h");
+        assert_eq!(relative(Duration::minutes(-45)), "45m overdue");
+        assert_eq!(relative(-Duration::days(2)), "2 days overdue");
+        assert_eq!(relative(Duration::days(1)), "in 1 day");
+        assert_eq!(relative(Duration::days(20)), "in 2 weeks");
+        assert_eq!(relative(Duration::seconds(20)), "now");
+        assert_eq!(relative(-Duration::days(800)), "2 years overdue");
+    }
+
+    #[test]
+    fn tables_fit_the_width_and_color_rows() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let tasks = [
+            task("A rather long description that will not fit +work", Some(now - Duration::hours(2))),
+            task("Soon", Some(now + Duration::hours(2))),
+            task("Later", None),
+        ];
+        let render = |style: &TableStyle| {
+            let mut out = Vec::new();
+            write_task_table(&mut out, &tasks, now, style).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let plain = render(&TableStyle { width: Some(70), ..TableStyle::plain() });
+        let lines: Vec<&str> = plain.lines().collect();
+        assert!(lines[0].starts_with("ID        Pri  Description"));
+        assert!(lines.iter().all(|line| line.width() <= 70), "{}", plain);
+        assert!(lines[1].contains("…") && lines[1].contains("2h overdue") && lines[1].ends_with("+work"));
+        assert!(!plain.contains('\x1b'));
+
+        let colored = render(&TableStyle { color: true, ..TableStyle::plain() });
+        let lines: Vec<&str> = colored.lines().collect();
+        assert!(lines[1].starts_with(Color::Red.escape()) && lines[2].starts_with(Color::Yellow.escape()));
+        assert!(!lines[3].contains('\x1b'));
+    }
+
+    #[test]
+    fn descriptions_stay_on_one_line() {
+        let now: DateTime<Utc> = "2026-10-14T12:00:00Z".parse().unwrap();
+        let mut done = task("Paid\nrent \x1b[31mtwice", None);
+        done.complete(now);
+        let tasks = [task("Call\r\nAlex\t\x07", None), done];
+        let mut out = Vec::new();
+        write_task_table(&mut out, &tasks, now, &TableStyle::plain()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().count(), 3, "{}", out);
+        assert!(out.contains("Call  Alex") && out.contains("[x] Paid rent  [31mtwice"), "{}", out);
+        assert!(!out.chars().any(|c| c.is_control() && c != '\n'), "{:?}", out);
+
+        let mut out = Vec::new();
+        write_task_list(&mut out, &tasks, &DateSettings::default()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(": [ ] Call  Alex  \n") && out.contains("[x] Paid rent  [31mtwice\n"), "{}", out);
+        let upcoming = [(now, &tasks[0])];
+        let mut out = Vec::new();
+        write_upcoming(&mut out, &upcoming, &DateSettings::default()).unwrap();
+        assert!(!String::from_utf8(out).unwrap().trim_end().contains(|c: char| c.is_control()));
+        let mut out = Vec::new();
+        crate::reminder::write_reminder(&mut out, &tasks[0], now).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Reminder: Task 'Call  Alex  ' is due!\n");
+
+        // Exports keep the text as it is.
+        let mut out = Vec::new();
+        write_csv_export(&mut out, &tasks).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(",\"Call\r\nAlex\t\x07\",") && out.contains(",\"Paid\nrent \x1b[31mtwice\","), "{:?}", out);
+    }
+}